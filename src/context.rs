@@ -1,18 +1,141 @@
-use std::collections::HashMap;
+use std::collections::{hash_map, HashMap, HashSet, VecDeque};
 use std::error;
 use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use {Name, Type, TypeSchema, Variable};
+use encoding::{context_from_bytes, context_to_bytes};
+use {DecodeError, Name, Type, TypeSchema, Variable};
 
 /// Errors during unification.
+///
+/// More kinds of error may be added in the future, so prefer the helper methods like
+/// [`is_occurs`] over matching exhaustively.
+///
+/// This implements [`std::error::Error`], so it converts with `?` into a boxed error. No
+/// variant wraps another error, so there is never a [`source`].
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate polytype;
+/// # use polytype::Context;
+/// # use std::error::Error;
+/// fn check(ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+///     ctx.unify(&tp!(list(tp!(0))), &tp!(list(tp!(int))))?;
+///     ctx.unify(&tp!(0), &tp!(bool))?;
+///     Ok(())
+/// }
+///
+/// # fn main() {
+/// let err = check(&mut Context::default()).unwrap_err();
+/// assert_eq!(err.to_string(), "Failure(int, bool)");
+/// assert!(err.source().is_none());
+/// # }
+/// ```
+///
+/// [`std::error::Error`]: https://doc.rust-lang.org/std/error/trait.Error.html
+/// [`source`]: https://doc.rust-lang.org/std/error/trait.Error.html#method.source
+/// [`is_occurs`]: #method.is_occurs
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum UnificationError<N: Name = &'static str> {
     /// `Occurs` happens when occurs checks fail (i.e. a type variable is
-    /// unified recursively). The id of the bad type variable is supplied.
+    /// unified recursively). The id of the bad type variable is supplied. Use
+    /// [`Context::occurs_info`] to find the type it would have been bound to.
+    ///
+    /// [`Context::occurs_info`]: struct.Context.html#method.occurs_info
     Occurs(Variable),
     /// `Failure` happens when symbols or type variants don't unify because of
     /// structural differences.
     Failure(Type<N>, Type<N>),
+    /// `FailureWithCause` is a `Failure` from [`Context::unify_labeled`], where the clash
+    /// involves a variable bound by an earlier labeled unification.
+    ///
+    /// [`Context::unify_labeled`]: struct.Context.html#method.unify_labeled
+    FailureWithCause {
+        /// The left side of the clash.
+        left: Type<N>,
+        /// The right side of the clash.
+        right: Type<N>,
+        /// The variable whose earlier binding led to the clash.
+        variable: Variable,
+        /// The label of the unification which bound `variable`.
+        cause: String,
+    },
+    /// `SubstitutionLimitExceeded` happens when unifying would make the context hold more
+    /// substitutions than its limit (see [`Context::set_max_substitution_size`]), which is
+    /// supplied.
+    ///
+    /// [`Context::set_max_substitution_size`]: struct.Context.html#method.set_max_substitution_size
+    SubstitutionLimitExceeded(usize),
+    /// `Cancelled` happens when the flag given to [`Context::unify_cancellable`] was set before
+    /// unification finished.
+    ///
+    /// [`Context::unify_cancellable`]: struct.Context.html#method.unify_cancellable
+    Cancelled,
+    /// `BindingBudgetExceeded` happens when unifying with [`Context::unify_bounded`] would
+    /// create more new bindings than its budget, which is supplied.
+    ///
+    /// [`Context::unify_bounded`]: struct.Context.html#method.unify_bounded
+    BindingBudgetExceeded(usize),
+    /// `NeverMismatch` is a `Failure` where one side is an uninhabited type (see
+    /// [`Context::set_never`]), which only unifies with itself or a variable.
+    ///
+    /// [`Context::set_never`]: struct.Context.html#method.set_never
+    NeverMismatch(Type<N>, Type<N>),
+}
+impl<N: Name> UnificationError<N> {
+    /// Whether this is an [`Occurs`] error.
+    ///
+    /// [`Occurs`]: #variant.Occurs
+    pub fn is_occurs(&self) -> bool {
+        matches!(*self, UnificationError::Occurs(_))
+    }
+    /// Whether this is a [`Failure`], [`FailureWithCause`] or [`NeverMismatch`] error.
+    ///
+    /// [`Failure`]: #variant.Failure
+    /// [`FailureWithCause`]: #variant.FailureWithCause
+    /// [`NeverMismatch`]: #variant.NeverMismatch
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            *self,
+            UnificationError::Failure(..)
+                | UnificationError::FailureWithCause { .. }
+                | UnificationError::NeverMismatch(..)
+        )
+    }
+    /// The pair of types that could not be unified, if there is one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let err = ctx.unify(&tp!(list(tp!(int))), &tp!(list(tp!(bool)))).unwrap_err();
+    /// assert!(err.is_failure());
+    /// assert_eq!(err.offending_types(), Some((&tp!(int), &tp!(bool))));
+    ///
+    /// let err = ctx.unify(&tp!(0), &tp!(list(tp!(0)))).unwrap_err();
+    /// assert!(err.is_occurs());
+    /// assert_eq!(err.offending_types(), None);
+    /// # }
+    /// ```
+    pub fn offending_types(&self) -> Option<(&Type<N>, &Type<N>)> {
+        match *self {
+            UnificationError::Failure(ref t1, ref t2)
+            | UnificationError::NeverMismatch(ref t1, ref t2)
+            | UnificationError::FailureWithCause {
+                left: ref t1,
+                right: ref t2,
+                ..
+            } => Some((t1, t2)),
+            _ => None,
+        }
+    }
 }
 impl<N: Name> fmt::Display for UnificationError<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -21,6 +144,29 @@ impl<N: Name> fmt::Display for UnificationError<N> {
             UnificationError::Failure(ref t1, ref t2) => {
                 write!(f, "Failure({}, {})", t1.show(false), t2.show(false))
             }
+            UnificationError::FailureWithCause {
+                ref left,
+                ref right,
+                variable,
+                ref cause,
+            } => write!(
+                f,
+                "Failure({}, {}) because t{} was bound by {}",
+                left.show(false),
+                right.show(false),
+                variable,
+                cause
+            ),
+            UnificationError::SubstitutionLimitExceeded(max) => {
+                write!(f, "SubstitutionLimitExceeded({})", max)
+            }
+            UnificationError::Cancelled => write!(f, "Cancelled"),
+            UnificationError::BindingBudgetExceeded(max) => {
+                write!(f, "BindingBudgetExceeded({})", max)
+            }
+            UnificationError::NeverMismatch(ref t1, ref t2) => {
+                write!(f, "NeverMismatch({}, {})", t1.show(false), t2.show(false))
+            }
         }
     }
 }
@@ -30,6 +176,303 @@ impl<N: Name + fmt::Debug> error::Error for UnificationError<N> {
     }
 }
 
+/// Errors from [`Context::check`], which keep track of which type was expected.
+///
+/// [`Context::check`]: struct.Context.html#method.check
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeError<N: Name = &'static str> {
+    /// `Mismatch` happens when the expected type and the actual type differ structurally,
+    /// including a clash with a skolem or an uninhabited type. The differing subterms are
+    /// supplied.
+    Mismatch {
+        /// The subterm of the expected type.
+        expected: Type<N>,
+        /// The subterm of the actual type.
+        actual: Type<N>,
+    },
+    /// `Unification` is any other failure to unify the types, such as an occurs check or an
+    /// exceeded substitution limit.
+    Unification(UnificationError<N>),
+}
+impl<N: Name> From<UnificationError<N>> for TypeError<N> {
+    fn from(err: UnificationError<N>) -> Self {
+        match err {
+            UnificationError::Failure(expected, actual)
+            | UnificationError::NeverMismatch(expected, actual)
+            | UnificationError::FailureWithCause {
+                left: expected,
+                right: actual,
+                ..
+            } => TypeError::Mismatch { expected, actual },
+            err => TypeError::Unification(err),
+        }
+    }
+}
+impl<N: Name> fmt::Display for TypeError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            TypeError::Mismatch {
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "expected {}, found {}",
+                expected.show(true),
+                actual.show(true)
+            ),
+            TypeError::Unification(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+impl<N: Name + fmt::Debug> error::Error for TypeError<N> {
+    fn description(&self) -> &'static str {
+        "type check failed"
+    }
+}
+
+/// A region of variables, created by [`Context::enter_scope`].
+///
+/// [`Context::enter_scope`]: struct.Context.html#method.enter_scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeId(Variable);
+
+/// An error from [`Context::leave_scope`]: a variable allocated within the scope is still free
+/// in the type bound to a variable from outside the scope.
+///
+/// [`Context::leave_scope`]: struct.Context.html#method.leave_scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeError {
+    /// The variable from within the scope which escaped.
+    pub variable: Variable,
+    /// The variable from outside the scope whose binding it escaped through.
+    pub via: Variable,
+}
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Escape(t{} via t{})", self.variable, self.via)
+    }
+}
+impl error::Error for EscapeError {
+    fn description(&self) -> &'static str {
+        "variable escaped its scope"
+    }
+}
+
+/// Errors from [`Context::check_against_signature`].
+///
+/// [`Context::check_against_signature`]: struct.Context.html#method.check_against_signature
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureError<N: Name = &'static str> {
+    /// The inferred type doesn't unify with the skolemized signature, e.g. because the
+    /// inferred type is less general than the signature.
+    Unification(UnificationError<N>),
+    /// A skolem of the signature escaped into the binding of a variable which isn't free in the
+    /// inferred type.
+    Escape(EscapeError),
+}
+impl<N: Name> From<UnificationError<N>> for SignatureError<N> {
+    fn from(err: UnificationError<N>) -> Self {
+        SignatureError::Unification(err)
+    }
+}
+impl<N: Name> From<EscapeError> for SignatureError<N> {
+    fn from(err: EscapeError) -> Self {
+        SignatureError::Escape(err)
+    }
+}
+impl<N: Name> fmt::Display for SignatureError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            SignatureError::Unification(ref err) => write!(f, "{}", err),
+            SignatureError::Escape(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+impl<N: Name + fmt::Debug> error::Error for SignatureError<N> {
+    fn description(&self) -> &'static str {
+        "type does not match its signature"
+    }
+}
+
+/// A broken invariant of a [`Context`], found by [`Context::check_invariants`]. Only available
+/// with the `test-invariants` feature.
+///
+/// [`Context`]: struct.Context.html
+/// [`Context::check_invariants`]: struct.Context.html#method.check_invariants
+#[cfg(feature = "test-invariants")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The variable is bound or mentioned by the substitution, but the context never allocated
+    /// it.
+    Unallocated(Variable),
+    /// The variable is reachable from its own binding.
+    Cycle(Variable),
+}
+#[cfg(feature = "test-invariants")]
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            InvariantViolation::Unallocated(v) => write!(f, "Unallocated({})", v),
+            InvariantViolation::Cycle(v) => write!(f, "Cycle({})", v),
+        }
+    }
+}
+#[cfg(feature = "test-invariants")]
+impl error::Error for InvariantViolation {
+    fn description(&self) -> &'static str {
+        "context invariant violated"
+    }
+}
+
+/// An error from [`Context::try_merge`], [`Context::rename_to_fresh`] or [`CowContext::fork`]:
+/// the resulting context would need more variables than a [`Variable`] can number.
+///
+/// [`Context::try_merge`]: struct.Context.html#method.try_merge
+/// [`Context::rename_to_fresh`]: struct.Context.html#method.rename_to_fresh
+/// [`CowContext::fork`]: struct.CowContext.html#method.fork
+/// [`Variable`]: type.Variable.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeOverflow;
+impl fmt::Display for MergeOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "MergeOverflow")
+    }
+}
+impl error::Error for MergeOverflow {
+    fn description(&self) -> &'static str {
+        "merged variables overflowed"
+    }
+}
+
+/// The binding which failed an occurs check, as found by [`Context::occurs_info`].
+///
+/// [`Context::occurs_info`]: struct.Context.html#method.occurs_info
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OccursInfo<N: Name = &'static str> {
+    /// The variable which would have been bound.
+    pub variable: Variable,
+    /// The type, containing `variable`, which it would have been bound to.
+    pub in_type: Type<N>,
+}
+impl<N: Name> fmt::Display for OccursInfo<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "t{} would be bound to {}",
+            self.variable,
+            self.in_type.show(true)
+        )
+    }
+}
+
+/// The bindings made by [`Context::unify_overlay`], which are only added to the context once
+/// given to [`Context::commit`].
+///
+/// [`Context::unify_overlay`]: struct.Context.html#method.unify_overlay
+/// [`Context::commit`]: struct.Context.html#method.commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlay<N: Name = &'static str> {
+    bindings: HashMap<Variable, Type<N>>,
+}
+impl<N: Name> Overlay<N> {
+    /// The substitutions which committing would add to the context.
+    pub fn bindings(&self) -> &HashMap<Variable, Type<N>> {
+        &self.bindings
+    }
+}
+
+/// Constraints beyond equality, checked as [`Context::unify_with_solver`] binds variables.
+///
+/// [`Context::unify_with_solver`]: struct.Context.html#method.unify_with_solver
+pub trait ConstraintSolver<N: Name> {
+    /// Called before `v` is bound to `t`. An error rejects the binding, failing the
+    /// unification with that error.
+    fn bind(&mut self, v: Variable, t: &Type<N>) -> Result<(), UnificationError<N>>;
+}
+
+/// A step of unification, as reported by [`Context::unify_events`].
+///
+/// [`Context::unify_events`]: struct.Context.html#method.unify_events
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnifyEvent<N: Name = &'static str> {
+    /// Unification moved into the argument at this index of both constructed types.
+    Descend(usize),
+    /// Unification finished with the arguments last descended into.
+    Ascend,
+    /// The two types were already equal, so nothing changed.
+    AlreadyEqual,
+    /// The variable was bound to the type.
+    Bound(Variable, Type<N>),
+}
+
+/// Equational axioms on constructors, for [`Context::unify_modulo`].
+///
+/// [`Context::unify_modulo`]: struct.Context.html#method.unify_modulo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Axioms<N: Name = &'static str> {
+    commutative: Vec<N>,
+}
+impl<N: Name> Default for Axioms<N> {
+    fn default() -> Self {
+        Axioms {
+            commutative: Vec::new(),
+        }
+    }
+}
+impl<N: Name> Axioms<N> {
+    /// Declare the binary constructor `name` commutative, so `name(a, b)` also unifies as
+    /// `name(b, a)`.
+    pub fn add_commutative(&mut self, name: N) {
+        if !self.commutative.contains(&name) {
+            self.commutative.push(name);
+        }
+    }
+    /// Whether `name` was declared commutative.
+    pub fn is_commutative(&self, name: &N) -> bool {
+        self.commutative.contains(name)
+    }
+}
+
+/// Implicit coercions between constructors, for [`Context::unify_coerce`].
+///
+/// [`Context::unify_coerce`]: struct.Context.html#method.unify_coerce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoercionTable<N: Name = &'static str> {
+    coercions: Vec<(N, N)>,
+}
+impl<N: Name> Default for CoercionTable<N> {
+    fn default() -> Self {
+        CoercionTable {
+            coercions: Vec::new(),
+        }
+    }
+}
+impl<N: Name> CoercionTable<N> {
+    /// Allow a type constructed by `from` to be coerced to one constructed by `to`. This
+    /// doesn't allow the opposite coercion.
+    pub fn add(&mut self, from: N, to: N) {
+        if !self.allows(&from, &to) {
+            self.coercions.push((from, to));
+        }
+    }
+    /// Whether a coercion from `from` to `to` was allowed.
+    pub fn allows(&self, from: &N, to: &N) -> bool {
+        self.coercions.iter().any(|(f, t)| f == from && t == to)
+    }
+}
+
+/// A coercion required by [`Context::unify_coerce`].
+///
+/// [`Context::unify_coerce`]: struct.Context.html#method.unify_coerce
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coercion<N: Name = &'static str> {
+    /// The subterm of the first type, which is coerced.
+    pub from: Type<N>,
+    /// The subterm of the second type, to which it is coerced.
+    pub to: Type<N>,
+}
+
 /// A type environment. Useful for reasoning about [`Type`]s (e.g unification,
 /// type inference).
 ///
@@ -39,64 +482,165 @@ impl<N: Name + fmt::Debug> error::Error for UnificationError<N> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Context<N: Name = &'static str> {
     pub(crate) substitution: HashMap<Variable, Type<N>>,
-    next: Variable,
+    pub(crate) next: Variable,
+    pub(crate) rigid: HashSet<Variable>,
+    pub(crate) labels: HashMap<Variable, String>,
+    pub(crate) max_substitution_size: Option<usize>,
+    pub(crate) dynamic: Vec<N>,
+    pub(crate) aliases: Vec<(N, Type<N>)>,
+    pub(crate) never: Vec<N>,
 }
 impl<N: Name> Default for Context<N> {
     fn default() -> Self {
         Context {
             substitution: HashMap::new(),
             next: 0,
+            rigid: HashSet::new(),
+            labels: HashMap::new(),
+            max_substitution_size: None,
+            dynamic: Vec::new(),
+            aliases: Vec::new(),
+            never: Vec::new(),
         }
     }
 }
+/// Iterates over each substitution in the context, in arbitrary order.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate polytype;
+/// # fn main() {
+/// # use polytype::Context;
+/// let mut ctx = Context::default();
+/// ctx.extend(0, tp!(int));
+/// ctx.extend(1, tp!(list(tp!(0))));
+///
+/// let mut bindings: Vec<_> = (&ctx).into_iter().collect();
+/// bindings.sort_by_key(|&(v, _)| v);
+/// assert_eq!(bindings, vec![(0, &tp!(int)), (1, &tp!(list(tp!(0))))]);
+///
+/// for (v, t) in &ctx {
+///     assert_eq!(ctx.substitution()[&v], *t);
+/// }
+/// # }
+/// ```
+impl<'a, N: Name> IntoIterator for &'a Context<N> {
+    type Item = (Variable, &'a Type<N>);
+    type IntoIter = Bindings<'a, N>;
+    fn into_iter(self) -> Bindings<'a, N> {
+        Bindings {
+            inner: self.substitution.iter(),
+        }
+    }
+}
+
+/// An iterator over the substitutions of a [`Context`].
+///
+/// [`Context`]: struct.Context.html
+#[derive(Debug, Clone)]
+pub struct Bindings<'a, N: Name + 'a> {
+    inner: hash_map::Iter<'a, Variable, Type<N>>,
+}
+impl<'a, N: Name> Iterator for Bindings<'a, N> {
+    type Item = (Variable, &'a Type<N>);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(v, t)| (*v, t))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
 impl<N: Name> Context<N> {
     /// The substitution managed by the context.
     pub fn substitution(&self) -> &HashMap<Variable, Type<N>> {
         &self.substitution
     }
-    /// Create a new substitution for [`Type::Variable`] number `v` to the
-    /// [`Type`] `t`.
+    /// The number of substitutions in the context.
     ///
-    /// [`Type`]: enum.Type.html
-    /// [`Type::Variable`]: enum.Type.html#variant.Variable
-    pub fn extend(&mut self, v: Variable, t: Type<N>) {
-        if v >= self.next {
-            self.next = v + 1
-        }
-        self.substitution.insert(v, t);
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// assert!(ctx.is_empty());
+    ///
+    /// ctx.unify(&tp!(list(tp!(0))), &tp!(list(tp!(int)))).unwrap();
+    /// assert_eq!(ctx.len(), 1);
+    /// assert!(!ctx.is_empty());
+    /// # }
+    /// ```
+    pub fn len(&self) -> usize {
+        self.substitution.len()
     }
-    /// Create a new [`Type::Variable`] from the next unused number.
+    /// Whether the context has no substitutions.
+    pub fn is_empty(&self) -> bool {
+        self.substitution.is_empty()
+    }
+    /// The type the variable is directly bound to, if any. Unlike [`Type::apply`], this
+    /// neither clones nor looks into the bound type, so it may itself be or contain bound
+    /// variables.
     ///
     /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
     /// # fn main() {
-    /// # use polytype::{Type, Context};
+    /// # use polytype::Context;
     /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(1));
+    /// ctx.extend(1, tp!(int));
     ///
-    /// // Get a fresh variable
-    /// let t0 = ctx.new_variable();
-    /// assert_eq!(t0, Type::Variable(0));
+    /// assert_eq!(ctx.resolve_shallow(0), Some(&tp!(1)));
+    /// assert_eq!(ctx.resolve_shallow(1), Some(&tp!(int)));
+    /// assert_eq!(ctx.resolve_shallow(2), None);
+    /// # }
+    /// ```
     ///
-    /// // Instantiating a polytype will yield new variables
-    /// let t = ptp!(0, 1; @arrow[tp!(0), tp!(1), tp!(1)]);
-    /// let t = t.instantiate(&mut ctx);
-    /// assert_eq!(t.to_string(), "t1 → t2 → t2");
+    /// [`Type::apply`]: enum.Type.html#method.apply
+    pub fn resolve_shallow(&self, v: Variable) -> Option<&Type<N>> {
+        self.substitution.get(&v)
+    }
+    /// Limit the number of substitutions the context may hold, or remove the limit with
+    /// `None`. Unification which would exceed the limit fails with
+    /// [`UnificationError::SubstitutionLimitExceeded`], as does [`extend_checked`]. This guards
+    /// against untrusted types which blow up the substitution.
     ///
-    /// // Get another fresh variable
-    /// let t3 = ctx.new_variable();
-    /// assert_eq!(t3, Type::Variable(3));
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    /// ctx.set_max_substitution_size(Some(2));
+    ///
+    /// let t1 = tp!(@arrow[tp!(0), tp!(1), tp!(2)]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(int), tp!(int)]);
+    /// assert_eq!(
+    ///     ctx.unify(&t1, &t2),
+    ///     Err(UnificationError::SubstitutionLimitExceeded(2))
+    /// );
+    /// assert!(ctx.is_empty());
     /// # }
     /// ```
     ///
-    /// [`Type::Variable`]: enum.Type.html#variant.Variable
-    pub fn new_variable(&mut self) -> Type<N> {
-        self.next += 1;
-        Type::Variable(self.next - 1)
+    /// [`UnificationError::SubstitutionLimitExceeded`]: enum.UnificationError.html#variant.SubstitutionLimitExceeded
+    /// [`extend_checked`]: #method.extend_checked
+    pub fn set_max_substitution_size(&mut self, max: Option<usize>) {
+        self.max_substitution_size = max;
     }
-    /// Create constraints within the context that ensure `t1` and `t2`
-    /// unify.
+    /// Fail if there isn't room for a substitution beyond `bindings`.
+    fn check_limit(&self, bindings: usize) -> Result<(), UnificationError<N>> {
+        match self.max_substitution_size {
+            Some(max) if bindings >= max => Err(UnificationError::SubstitutionLimitExceeded(max)),
+            _ => Ok(()),
+        }
+    }
+    /// Register `name` as the dynamic type of gradual typing: a type constructed with it
+    /// unifies with any type, without binding anything.
     ///
     /// # Examples
     ///
@@ -105,65 +649,503 @@ impl<N: Name> Context<N> {
     /// # fn main() {
     /// # use polytype::Context;
     /// let mut ctx = Context::default();
+    /// ctx.set_dynamic("dyn");
     ///
-    /// let t1 = tp!(@arrow[tp!(int), tp!(0)]);
-    /// let t2 = tp!(@arrow[tp!(1), tp!(bool)]);
-    /// ctx.unify(&t1, &t2).expect("unifies");
+    /// let t1 = tp!(@arrow[tp!(dyn), tp!(0)]);
+    /// let t2 = tp!(@arrow[tp!(list(tp!(1))), tp!(int)]);
+    /// ctx.unify(&t1, &t2).expect("dyn is consistent with list(t1)");
+    /// assert_eq!(tp!(0).apply(&ctx), tp!(int));
+    /// assert_eq!(tp!(1).apply(&ctx), tp!(1));
     ///
-    /// let t1 = t1.apply(&ctx);
-    /// let t2 = t2.apply(&ctx);
-    /// assert_eq!(t1, t2);  // int → bool
+    /// assert!(ctx.unify(&tp!(list(tp!(dyn))), &tp!(int)).is_err());
     /// # }
     /// ```
+    pub fn set_dynamic(&mut self, name: N) {
+        if !self.dynamic.contains(&name) {
+            self.dynamic.push(name);
+        }
+    }
+    /// Register `name` as an uninhabited type, such as the type of unreachable code. Like any
+    /// type, it unifies with itself and with variables, but a clash with another constructor is
+    /// reported as [`UnificationError::NeverMismatch`] rather than a plain `Failure`.
     ///
-    /// Unification errors leave the context unaffected. A
-    /// [`UnificationError::Failure`] error happens when symbols don't match:
+    /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
     /// # fn main() {
     /// # use polytype::{Context, UnificationError};
     /// let mut ctx = Context::default();
+    /// ctx.set_never("never");
     ///
-    /// let t1 = tp!(@arrow[tp!(int), tp!(0)]);
-    /// let t2 = tp!(@arrow[tp!(bool), tp!(1)]);
-    /// let res = ctx.unify(&t1, &t2);
-    ///
-    /// if let Err(UnificationError::Failure(left, right)) = res {
-    ///     // failed to unify t1 with t2.
-    ///     assert_eq!(left, tp!(int));
-    ///     assert_eq!(right, tp!(bool));
-    /// } else { unreachable!() }
+    /// ctx.unify(&tp!(list(tp!(0))), &tp!(list(tp!(never)))).expect("unifies");
+    /// assert_eq!(
+    ///     ctx.unify(&tp!(0), &tp!(int)),
+    ///     Err(UnificationError::NeverMismatch(tp!(never), tp!(int)))
+    /// );
     /// # }
     /// ```
     ///
-    /// An [`UnificationError::Occurs`] error happens when the same type
-    /// variable occurs in both types in a circular way. Ensure you
-    /// [`instantiate`][] your types properly, so type variables don't overlap
-    /// unless you mean them to.
+    /// [`UnificationError::NeverMismatch`]: enum.UnificationError.html#variant.NeverMismatch
+    pub fn set_never(&mut self, name: N) {
+        if !self.never.contains(&name) {
+            self.never.push(name);
+        }
+    }
+    /// The error for types whose constructors mismatch.
+    fn failure(&self, t1: Type<N>, t2: Type<N>) -> UnificationError<N> {
+        let is_never = |t: &Type<N>| match *t {
+            Type::Constructed(ref name, _) => self.never.contains(name),
+            Type::Variable(_) => false,
+        };
+        if is_never(&t1) || is_never(&t2) {
+            UnificationError::NeverMismatch(t1, t2)
+        } else {
+            UnificationError::Failure(t1, t2)
+        }
+    }
+    fn is_dynamic(&self, t: &Type<N>) -> bool {
+        match *t {
+            Type::Constructed(ref name, _) => self.dynamic.contains(name),
+            Type::Variable(_) => false,
+        }
+    }
+    /// Register `name` as an alias which unification expands to `expansion` when it doesn't
+    /// directly match the other type. Variable `ti` of `expansion` stands for the `i`th
+    /// argument of the alias. Aliases must not be recursive.
+    ///
+    /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
     /// # fn main() {
-    /// # use polytype::{Context, UnificationError};
+    /// # use polytype::Context;
     /// let mut ctx = Context::default();
+    /// ctx.add_alias("string", tp!(list(tp!(char))));
+    /// ctx.add_alias("dict", tp!(list(tp!(pair(tp!(string), tp!(0))))));
     ///
-    /// let t1 = tp!(1);
-    /// let t2 = tp!(@arrow[tp!(bool), tp!(1)]);
-    /// let res = ctx.unify(&t1, &t2);
+    /// ctx.unify(&tp!(string), &tp!(list(tp!(0)))).expect("string is list(char)");
+    /// assert_eq!(tp!(0).apply(&ctx), tp!(char));
     ///
-    /// if let Err(UnificationError::Occurs(v)) = res {
-    ///     // failed to unify t1 with t2 because of circular type variable occurrence.
-    ///     // t1 would have to be bool -> bool -> ... ad infinitum.
-    ///     assert_eq!(v, 1);
-    /// } else { unreachable!() }
+    /// let t = tp!(list(tp!(pair(tp!(list(tp!(char))), tp!(1)))));
+    /// ctx.unify(&tp!(dict(tp!(int))), &t).expect("aliases expand within aliases");
+    /// assert_eq!(tp!(1).apply(&ctx), tp!(int));
+    /// assert!(ctx.unify(&tp!(string), &tp!(int)).is_err());
     /// # }
     /// ```
-    ///
-    /// [`UnificationError::Failure`]: enum.UnificationError.html#variant.Failure
-    /// [`UnificationError::Occurs`]: enum.UnificationError.html#variant.Occurs
-    /// [`instantiate`]: enum.Type.html#method.instantiate
-    pub fn unify(&mut self, t1: &Type<N>, t2: &Type<N>) -> Result<(), UnificationError<N>> {
+    pub fn add_alias(&mut self, name: N, expansion: Type<N>) {
+        self.aliases.retain(|(n, _)| *n != name);
+        self.aliases.push((name, expansion));
+    }
+    /// Expands an alias, substituting its arguments into its expansion.
+    fn expand_alias(&self, t: &Type<N>) -> Option<Type<N>> {
+        match *t {
+            Type::Constructed(ref name, ref args) => {
+                let (_, expansion) = self.aliases.iter().find(|(n, _)| n == name)?;
+                let arguments = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| (i as Variable, arg.clone()))
+                    .collect();
+                Some(expansion.substitute(&arguments))
+            }
+            Type::Variable(_) => None,
+        }
+    }
+    /// When the constructors of `t1` and `t2` differ, expand whichever is an alias.
+    fn expand_mismatch(&self, t1: &Type<N>, t2: &Type<N>) -> Option<(Type<N>, Type<N>)> {
+        if !constructors_mismatch(t1, t2) {
+            return None;
+        }
+        match self.expand_alias(t1) {
+            Some(t1) => Some((t1, t2.clone())),
+            None => self.expand_alias(t2).map(|t2| (t1.clone(), t2)),
+        }
+    }
+    fn is_alias(&self, t: &Type<N>) -> bool {
+        match *t {
+            Type::Constructed(ref name, _) => self.aliases.iter().any(|(n, _)| n == name),
+            Type::Variable(_) => false,
+        }
+    }
+    /// Like constructors_mismatch, but a dynamic type or an alias never mismatches.
+    fn mismatch(&self, t1: &Type<N>, t2: &Type<N>) -> bool {
+        constructors_mismatch(t1, t2)
+            && !self.is_dynamic(t1)
+            && !self.is_dynamic(t2)
+            && !self.is_alias(t1)
+            && !self.is_alias(t2)
+    }
+    /// Create a new substitution for [`Type::Variable`] number `v` to the
+    /// [`Type`] `t`.
+    ///
+    /// [`Type`]: enum.Type.html
+    /// [`Type::Variable`]: enum.Type.html#variant.Variable
+    pub fn extend(&mut self, v: Variable, t: Type<N>) {
+        if v >= self.next {
+            self.next = v + 1
+        }
+        self.substitution.insert(v, t);
+    }
+    /// Like [`extend`], but fails with [`UnificationError::Occurs`] instead of creating a
+    /// cyclic substitution, leaving the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    /// ctx.extend_checked(0, tp!(list(tp!(1)))).expect("no cycle");
+    ///
+    /// // t1 := t0 would make t0 = list(list(list(...)))
+    /// let res = ctx.extend_checked(1, tp!(0));
+    /// assert_eq!(res, Err(UnificationError::Occurs(1)));
+    /// assert_eq!(ctx.substitution().len(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`extend`]: #method.extend
+    /// [`UnificationError::Occurs`]: enum.UnificationError.html#variant.Occurs
+    pub fn extend_checked(&mut self, v: Variable, t: Type<N>) -> Result<(), UnificationError<N>> {
+        if self.occurs_under(v, &t, &mut HashSet::new()) {
+            return Err(UnificationError::Occurs(v));
+        }
+        if !self.substitution.contains_key(&v) {
+            self.check_limit(self.substitution.len())?;
+        }
+        self.extend(v, t);
+        Ok(())
+    }
+    /// Whether `v` occurs in `t` once the substitution is fully applied.
+    fn occurs_under(&self, v: Variable, t: &Type<N>, seen: &mut HashSet<Variable>) -> bool {
+        match *t {
+            Type::Variable(w) if w == v => true,
+            Type::Variable(w) if !seen.insert(w) => false,
+            Type::Variable(w) => match self.substitution.get(&w) {
+                Some(t) => self.occurs_under(v, t, seen),
+                None => false,
+            },
+            Type::Constructed(_, ref args) => args.iter().any(|t| self.occurs_under(v, t, seen)),
+        }
+    }
+    /// Create a new [`Type::Variable`] from the next unused number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Type, Context};
+    /// let mut ctx = Context::default();
+    ///
+    /// // Get a fresh variable
+    /// let t0 = ctx.new_variable();
+    /// assert_eq!(t0, Type::Variable(0));
+    ///
+    /// // Instantiating a polytype will yield new variables
+    /// let t = ptp!(0, 1; @arrow[tp!(0), tp!(1), tp!(1)]);
+    /// let t = t.instantiate(&mut ctx);
+    /// assert_eq!(t.to_string(), "t1 → t2 → t2");
+    ///
+    /// // Get another fresh variable
+    /// let t3 = ctx.new_variable();
+    /// assert_eq!(t3, Type::Variable(3));
+    /// # }
+    /// ```
+    ///
+    /// [`Type::Variable`]: enum.Type.html#variant.Variable
+    pub fn new_variable(&mut self) -> Type<N> {
+        self.next += 1;
+        Type::Variable(self.next - 1)
+    }
+    /// Create a new skolem: a rigid [`Type::Variable`] which unification never binds, so it only
+    /// unifies with itself or with a variable which isn't rigid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let a = ctx.new_skolem();
+    /// let b = ctx.new_skolem();
+    /// assert!(ctx.unify(&a, &a).is_ok());
+    /// assert!(ctx.unify(&a, &tp!(int)).is_err());
+    /// assert!(ctx.unify(&a, &b).is_err());
+    ///
+    /// let c = ctx.new_variable();
+    /// ctx.unify(&c, &tp!(list(a.clone()))).expect("flexible variables bind to skolems");
+    /// assert_eq!(c.apply(&ctx), tp!(list(a)));
+    /// # }
+    /// ```
+    ///
+    /// [`Type::Variable`]: enum.Type.html#variant.Variable
+    pub fn new_skolem(&mut self) -> Type<N> {
+        self.rigid.insert(self.next);
+        self.new_variable()
+    }
+    /// Whether the variable is a skolem, as created by [`new_skolem`].
+    ///
+    /// [`new_skolem`]: #method.new_skolem
+    pub fn is_rigid(&self, v: Variable) -> bool {
+        self.rigid.contains(&v)
+    }
+    /// Instantiate a group of [`TypeSchema`]s which share some of their quantified variables,
+    /// such as those of a mutually recursive group of definitions.
+    ///
+    /// Quantified variables listed in `shared` are replaced with the same fresh variable in
+    /// every schema, while all others are replaced with distinct fresh variables as with
+    /// [`TypeSchema::instantiate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let even = ptp!(0, 1; @arrow[tp!(0), tp!(1)]);
+    /// let odd = ptp!(0, 1; @arrow[tp!(1), tp!(0)]);
+    ///
+    /// let ts = ctx.instantiate_group(&[even, odd], &[0]);
+    /// assert_eq!(ts[0].to_string(), "t0 → t1");
+    /// assert_eq!(ts[1].to_string(), "t2 → t0");
+    /// # }
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`TypeSchema::instantiate`]: enum.TypeSchema.html#method.instantiate
+    pub fn instantiate_group(
+        &mut self,
+        schemas: &[TypeSchema<N>],
+        shared: &[Variable],
+    ) -> Vec<Type<N>> {
+        let mut fresh_shared: HashMap<Variable, Type<N>> = HashMap::new();
+        schemas
+            .iter()
+            .map(|schema| {
+                let mut substitution = HashMap::new();
+                let mut schema = schema;
+                while let TypeSchema::Polytype { variable, ref body } = *schema {
+                    let t = if shared.contains(&variable) {
+                        fresh_shared
+                            .entry(variable)
+                            .or_insert_with(|| self.new_variable())
+                            .clone()
+                    } else {
+                        self.new_variable()
+                    };
+                    substitution.insert(variable, t);
+                    schema = body;
+                }
+                match *schema {
+                    TypeSchema::Monotype(ref t) => t.substitute(&substitution),
+                    TypeSchema::Polytype { .. } => unreachable!(),
+                }
+            })
+            .collect()
+    }
+    /// Begin a scope: every variable allocated from now on belongs to it, until it is left with
+    /// [`leave_scope`].
+    ///
+    /// [`leave_scope`]: #method.leave_scope
+    pub fn enter_scope(&mut self) -> ScopeId {
+        ScopeId(self.next)
+    }
+    /// End a scope, checking that none of its variables escaped: no variable allocated within
+    /// the scope may remain free in what a variable from before the scope is bound to.
+    ///
+    /// This catches, for example, a skolem escaping in rank-N type checking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, EscapeError};
+    /// let mut ctx = Context::default();
+    /// let outer = ctx.new_variable();
+    ///
+    /// let scope = ctx.enter_scope();
+    /// let local = ctx.new_variable();
+    /// ctx.unify(&local, &tp!(int)).unwrap();
+    /// ctx.unify(&outer, &tp!(list(local))).unwrap();
+    /// // the local variable is bound, so it doesn't escape
+    /// assert_eq!(ctx.leave_scope(scope), Ok(()));
+    ///
+    /// let scope = ctx.enter_scope();
+    /// let outer = ctx.new_variable();
+    /// let inner_scope = ctx.enter_scope();
+    /// let local = ctx.new_variable();
+    /// ctx.unify(&outer, &tp!(list(local))).unwrap();
+    /// // the local variable is free in outer's binding
+    /// assert_eq!(
+    ///     ctx.leave_scope(inner_scope),
+    ///     Err(EscapeError { variable: 3, via: 2 })
+    /// );
+    /// // but it doesn't escape the enclosing scope
+    /// assert_eq!(ctx.leave_scope(scope), Ok(()));
+    /// # }
+    /// ```
+    pub fn leave_scope(&mut self, scope: ScopeId) -> Result<(), EscapeError> {
+        let ScopeId(start) = scope;
+        let mut outer: Vec<_> = self
+            .substitution
+            .keys()
+            .cloned()
+            .filter(|&v| v < start)
+            .collect();
+        outer.sort_unstable();
+        for via in outer {
+            let tp = self.resolve(&Type::Variable(via));
+            if let Some(variable) = tp.vars().into_iter().filter(|&w| w >= start).min() {
+                return Err(EscapeError { variable, via });
+            }
+        }
+        Ok(())
+    }
+    /// Check a definition's `inferred` type against its `declared` signature: the signature's
+    /// quantified variables are skolemized, so the inferred type must be at least as general as
+    /// the signature to unify with it.
+    ///
+    /// Variables free in `inferred` belong to the definition, so they may be bound to skolems.
+    /// Any other variable of the context, such as one from an enclosing definition, may not,
+    /// or the skolem would escape as with [`leave_scope`]. An error leaves the context
+    /// unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, EscapeError, SignatureError};
+    /// let declared = ptp!(0; @arrow[tp!(0), tp!(0)]); // ∀α. α → α
+    ///
+    /// let mut ctx = Context::default();
+    /// let x = ctx.new_variable();
+    /// let inferred = tp!(@arrow[x.clone(), x]);
+    /// ctx.check_against_signature(&inferred, &declared).expect("as general");
+    ///
+    /// let inferred = tp!(@arrow[tp!(int), tp!(int)]);
+    /// assert!(ctx.check_against_signature(&inferred, &declared).is_err());
+    ///
+    /// // x is shared with the enclosing definition through outer.
+    /// let mut ctx = Context::default();
+    /// let outer = ctx.new_variable();
+    /// let x = ctx.new_variable();
+    /// ctx.unify(&outer, &tp!(list(x.clone()))).unwrap();
+    /// let inferred = tp!(@arrow[x.clone(), x]);
+    /// assert_eq!(
+    ///     ctx.check_against_signature(&inferred, &declared),
+    ///     Err(SignatureError::Escape(EscapeError { variable: 2, via: 0 }))
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`leave_scope`]: #method.leave_scope
+    pub fn check_against_signature(
+        &mut self,
+        inferred: &Type<N>,
+        declared: &TypeSchema<N>,
+    ) -> Result<(), SignatureError<N>> {
+        let own = inferred.apply(self).vars();
+        let mut ctx = self.clone();
+        let ScopeId(start) = ctx.enter_scope();
+        let skolemized = declared.skolemize(&mut ctx);
+        ctx.unify(inferred, &skolemized)?;
+        let mut outer: Vec<_> = ctx
+            .substitution
+            .keys()
+            .cloned()
+            .filter(|&v| v < start && !own.contains(&v))
+            .collect();
+        outer.sort_unstable();
+        for via in outer {
+            let tp = ctx.resolve(&Type::Variable(via));
+            if let Some(variable) = tp.vars().into_iter().filter(|&w| w >= start).min() {
+                return Err(SignatureError::Escape(EscapeError { variable, via }));
+            }
+        }
+        *self = ctx;
+        Ok(())
+    }
+    /// Create constraints within the context that ensure `t1` and `t2`
+    /// unify.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(@arrow[tp!(int), tp!(0)]);
+    /// let t2 = tp!(@arrow[tp!(1), tp!(bool)]);
+    /// ctx.unify(&t1, &t2).expect("unifies");
+    ///
+    /// let t1 = t1.apply(&ctx);
+    /// let t2 = t2.apply(&ctx);
+    /// assert_eq!(t1, t2);  // int → bool
+    /// # }
+    /// ```
+    ///
+    /// Unification errors leave the context unaffected. A
+    /// [`UnificationError::Failure`] error happens when symbols don't match:
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(@arrow[tp!(int), tp!(0)]);
+    /// let t2 = tp!(@arrow[tp!(bool), tp!(1)]);
+    /// let res = ctx.unify(&t1, &t2);
+    ///
+    /// if let Err(UnificationError::Failure(left, right)) = res {
+    ///     // failed to unify t1 with t2.
+    ///     assert_eq!(left, tp!(int));
+    ///     assert_eq!(right, tp!(bool));
+    /// } else { unreachable!() }
+    /// # }
+    /// ```
+    ///
+    /// An [`UnificationError::Occurs`] error happens when the same type
+    /// variable occurs in both types in a circular way. Ensure you
+    /// [`instantiate`][] your types properly, so type variables don't overlap
+    /// unless you mean them to.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(1);
+    /// let t2 = tp!(@arrow[tp!(bool), tp!(1)]);
+    /// let res = ctx.unify(&t1, &t2);
+    ///
+    /// if let Err(UnificationError::Occurs(v)) = res {
+    ///     // failed to unify t1 with t2 because of circular type variable occurrence.
+    ///     // t1 would have to be bool -> bool -> ... ad infinitum.
+    ///     assert_eq!(v, 1);
+    /// } else { unreachable!() }
+    /// # }
+    /// ```
+    ///
+    /// [`UnificationError::Failure`]: enum.UnificationError.html#variant.Failure
+    /// [`UnificationError::Occurs`]: enum.UnificationError.html#variant.Occurs
+    /// [`instantiate`]: enum.Type.html#method.instantiate
+    pub fn unify(&mut self, t1: &Type<N>, t2: &Type<N>) -> Result<(), UnificationError<N>> {
+        if self.mismatch(t1, t2) {
+            // no need to copy the context when the outermost constructors already differ.
+            return Err(self.failure(t1.apply(self), t2.apply(self)));
+        }
         let mut t1 = t1.clone();
         let mut t2 = t2.clone();
         t1.apply_mut(self);
@@ -173,195 +1155,2250 @@ impl<N: Name> Context<N> {
         *self = ctx;
         Ok(())
     }
-    /// Like [`unify`], but may affect the context even under failure. Hence, use this if you
-    /// discard the context upon failure.
+    /// Like [`unify`], but takes ownership of the types rather than copying them.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.unify_owned(tp!(list(tp!(0))), tp!(list(tp!(int)))).expect("unifies");
+    /// assert_eq!(tp!(0).apply(&ctx), tp!(int));
+    ///
+    /// assert!(ctx.unify_owned(tp!(0), tp!(bool)).is_err());
+    /// assert_eq!(tp!(0).apply(&ctx), tp!(int));
+    /// # }
+    /// ```
     ///
     /// [`unify`]: #method.unify
-    pub fn unify_fast(
+    pub fn unify_owned(
         &mut self,
         mut t1: Type<N>,
         mut t2: Type<N>,
     ) -> Result<(), UnificationError<N>> {
         t1.apply_mut(self);
         t2.apply_mut(self);
-        self.unify_internal(t1, t2)
-    }
-    /// unify_internal may mutate the context even with an error. The context on
-    /// which it's called should be discarded if there's an error.
-    fn unify_internal(&mut self, t1: Type<N>, t2: Type<N>) -> Result<(), UnificationError<N>> {
-        if t1 == t2 {
-            return Ok(());
+        if self.mismatch(&t1, &t2) {
+            return Err(self.failure(t1, t2));
         }
-        match (t1, t2) {
-            (Type::Variable(v), t2) => {
-                if t2.occurs(v) {
-                    Err(UnificationError::Occurs(v))
-                } else {
-                    self.extend(v, t2.clone());
-                    Ok(())
-                }
-            }
-            (t1, Type::Variable(v)) => {
-                if t1.occurs(v) {
-                    Err(UnificationError::Occurs(v))
-                } else {
-                    self.extend(v, t1.clone());
-                    Ok(())
-                }
-            }
-            (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
-                if n1 != n2 {
-                    Err(UnificationError::Failure(
-                        Type::Constructed(n1, a1),
-                        Type::Constructed(n2, a2),
-                    ))
-                } else {
-                    for (mut t1, mut t2) in a1.into_iter().zip(a2) {
-                        t1.apply_mut(self);
-                        t2.apply_mut(self);
-                        self.unify_internal(t1, t2)?;
+        let mut ctx = self.clone();
+        ctx.unify_internal(t1, t2)?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`unify`], but reports whether any progress was made: `Ok(true)` if new bindings were
+    /// added to unify the types, and `Ok(false)` if they already unified without any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(list(tp!(0)));
+    /// let t2 = tp!(list(tp!(int)));
+    /// assert_eq!(ctx.unify_progress(&t1, &t2), Ok(true));
+    /// assert_eq!(ctx.unify_progress(&t1, &t2), Ok(false));
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_progress(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<bool, UnificationError<N>> {
+        // types can unify without being equal, e.g. through a dynamic type or an alias, so
+        // progress is new bindings rather than a difference between the types.
+        let before = self.len();
+        self.unify(t1, t2)?;
+        Ok(self.len() > before)
+    }
+    /// Whether the two variables are already equal under the context's substitution, without
+    /// unifying them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.unify(&tp!(0), &tp!(1)).unwrap();
+    /// ctx.unify(&tp!(2), &tp!(list(tp!(int)))).unwrap();
+    /// ctx.unify(&tp!(3), &tp!(list(tp!(int)))).unwrap();
+    ///
+    /// assert!(ctx.same(0, 1));
+    /// assert!(ctx.same(2, 3));
+    /// assert!(!ctx.same(0, 2));
+    /// # }
+    /// ```
+    pub fn same(&self, a: Variable, b: Variable) -> bool {
+        a == b || self.resolve(&Type::Variable(a)) == self.resolve(&Type::Variable(b))
+    }
+    /// Resolve the type fully, leaving in place any variable whose binding refers back to it.
+    fn resolve(&self, t: &Type<N>) -> Type<N> {
+        t.zonk(self)
+    }
+    /// Check whether `t1` and `t2` unify once each pair in `assumptions` has been unified,
+    /// without affecting the context.
+    ///
+    /// An error is returned if the assumptions themselves don't unify. Otherwise, the result
+    /// says whether `t1` and `t2` unify under them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let ctx = Context::default();
+    ///
+    /// let assumptions = vec![(tp!(0), tp!(int))];
+    /// assert_eq!(ctx.unify_assuming(&assumptions, &tp!(0), &tp!(int)), Ok(true));
+    /// assert_eq!(ctx.unify_assuming(&assumptions, &tp!(0), &tp!(bool)), Ok(false));
+    ///
+    /// // the context itself was never touched
+    /// assert!(ctx.substitution().is_empty());
+    ///
+    /// let contradictory = vec![(tp!(0), tp!(int)), (tp!(0), tp!(bool))];
+    /// assert!(ctx.unify_assuming(&contradictory, &tp!(0), &tp!(1)).is_err());
+    /// # }
+    /// ```
+    pub fn unify_assuming(
+        &self,
+        assumptions: &[(Type<N>, Type<N>)],
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<bool, UnificationError<N>> {
+        let mut ctx = self.clone();
+        for (a1, a2) in assumptions {
+            ctx.unify_fast(a1.clone(), a2.clone())?;
+        }
+        Ok(ctx.unify_fast(t1.clone(), t2.clone()).is_ok())
+    }
+    /// Unify each pair of `constraints` in order, keeping those that succeed and skipping those
+    /// that fail. The result says, for each constraint, whether it was kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let constraints = vec![
+    ///     (tp!(0), tp!(int)),
+    ///     (tp!(0), tp!(bool)), // conflicts with the first constraint
+    ///     (tp!(1), tp!(list(tp!(0)))),
+    /// ];
+    /// assert_eq!(ctx.unify_soft(&constraints), vec![true, false, true]);
+    /// assert_eq!(tp!(1).apply(&ctx), tp!(list(tp!(int))));
+    /// # }
+    /// ```
+    pub fn unify_soft(&mut self, constraints: &[(Type<N>, Type<N>)]) -> Vec<bool> {
+        constraints
+            .iter()
+            .map(|(t1, t2)| self.unify(t1, t2).is_ok())
+            .collect()
+    }
+    /// Check each pair of `checks` independently, without affecting the context, returning
+    /// the index and error of each that fails to unify. Checking stops once `max_errors`
+    /// failures are found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let ctx = Context::default();
+    ///
+    /// let checks = vec![
+    ///     (tp!(0), tp!(int)),
+    ///     (tp!(int), tp!(bool)),
+    ///     (tp!(0), tp!(bool)), // independent of the first check
+    ///     (tp!(list(tp!(0))), tp!(0)),
+    /// ];
+    /// let errors = ctx.check_all(&checks, 5);
+    /// let indices: Vec<_> = errors.iter().map(|&(i, _)| i).collect();
+    /// assert_eq!(indices, vec![1, 3]);
+    ///
+    /// assert_eq!(ctx.check_all(&checks, 1).len(), 1);
+    /// # }
+    /// ```
+    pub fn check_all(
+        &self,
+        checks: &[(Type<N>, Type<N>)],
+        max_errors: usize,
+    ) -> Vec<(usize, UnificationError<N>)> {
+        let mut errors = Vec::new();
+        for (i, (t1, t2)) in checks.iter().enumerate() {
+            if errors.len() >= max_errors {
+                break;
+            }
+            if let Err(e) = self.clone().unify_fast(t1.clone(), t2.clone()) {
+                errors.push((i, e));
+            }
+        }
+        errors
+    }
+    /// Unify every pair of `constraints`, passing over them repeatedly until a full pass makes
+    /// no progress (see [`unify_progress`]).
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let constraints = vec![
+    ///     (tp!(2), tp!(list(tp!(1)))),
+    ///     (tp!(1), tp!(0)),
+    ///     (tp!(0), tp!(int)),
+    /// ];
+    /// ctx.solve(constraints).expect("consistent constraints");
+    /// // t2 is now equal to list(int)
+    /// assert_eq!(ctx.unify_progress(&tp!(2), &tp!(list(tp!(int)))), Ok(false));
+    ///
+    /// let constraints = vec![(tp!(3), tp!(0)), (tp!(3), tp!(bool))];
+    /// assert!(ctx.solve(constraints).is_err());
+    /// assert_eq!(tp!(3).apply(&ctx), tp!(3));
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`unify_progress`]: #method.unify_progress
+    pub fn solve(
+        &mut self,
+        constraints: Vec<(Type<N>, Type<N>)>,
+    ) -> Result<(), UnificationError<N>> {
+        let mut ctx = self.clone();
+        loop {
+            let mut progress = false;
+            for (t1, t2) in &constraints {
+                progress |= ctx.unify_progress(t1, t2)?;
+            }
+            if !progress {
+                break;
+            }
+        }
+        *self = ctx;
+        Ok(())
+    }
+    /// The principal type of `query` under `constraints`: [`solve`] the constraints, then apply
+    /// the context to `query` until no bound variables remain in it.
+    ///
+    /// Like [`solve`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let constraints = vec![
+    ///     (tp!(0), tp!(@arrow[tp!(1), tp!(2)])),
+    ///     (tp!(2), tp!(list(tp!(1)))),
+    /// ];
+    /// let query = tp!(pair(tp!(0), tp!(3)));
+    /// assert_eq!(
+    ///     ctx.principal(constraints, &query),
+    ///     Ok(tp!(pair(tp!(@arrow[tp!(1), tp!(list(tp!(1)))]), tp!(3))))
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`solve`]: #method.solve
+    pub fn principal(
+        &mut self,
+        constraints: Vec<(Type<N>, Type<N>)>,
+        query: &Type<N>,
+    ) -> Result<Type<N>, UnificationError<N>> {
+        self.solve(constraints)?;
+        Ok(self.resolve(query))
+    }
+    /// Like [`unify`], but for checking `actual` against an `expected` type: a mismatch is a
+    /// [`TypeError::Mismatch`], which says which of the differing subterms came from which type.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, TypeError};
+    /// let mut ctx = Context::default();
+    ///
+    /// let expected = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// let actual = tp!(@arrow[tp!(1), tp!(list(tp!(bool)))]);
+    /// ctx.check(&expected, &actual).expect("unifies");
+    ///
+    /// let err = ctx.check(&tp!(list(tp!(int))), &tp!(1)).unwrap_err();
+    /// assert_eq!(
+    ///     err,
+    ///     TypeError::Mismatch {
+    ///         expected: tp!(list(tp!(int))),
+    ///         actual: tp!(int),
+    ///     }
+    /// );
+    /// assert_eq!(err.to_string(), "expected list(int), found int");
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`TypeError::Mismatch`]: enum.TypeError.html#variant.Mismatch
+    pub fn check(&mut self, expected: &Type<N>, actual: &Type<N>) -> Result<(), TypeError<N>> {
+        // unification keeps the left side on the left as it descends into the types.
+        self.unify(expected, actual).map_err(TypeError::from)
+    }
+    /// How far `t1` and `t2` are from unifying: the number of mismatched subterms found by
+    /// descending through constructors they share, with bindable variables unifying freely.
+    /// This is `0` exactly when they unify. The context is unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let ctx = Context::default();
+    /// let expected = tp!(@arrow[tp!(int), tp!(list(tp!(0))), tp!(0)]);
+    ///
+    /// let t = tp!(@arrow[tp!(1), tp!(list(tp!(bool))), tp!(bool)]);
+    /// assert_eq!(ctx.unify_distance(&expected, &t), 0);
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(bool))), tp!(int)]);
+    /// assert_eq!(ctx.unify_distance(&expected, &t), 1);
+    /// let t = tp!(@arrow[tp!(bool), tp!(int), tp!(int)]);
+    /// assert_eq!(ctx.unify_distance(&expected, &t), 2);
+    /// # }
+    /// ```
+    pub fn unify_distance(&self, t1: &Type<N>, t2: &Type<N>) -> usize {
+        self.clone().unify_distance_internal(t1, t2)
+    }
+    fn unify_distance_internal(&mut self, t1: &Type<N>, t2: &Type<N>) -> usize {
+        let t1 = t1.apply(self);
+        let t2 = t2.apply(self);
+        if self.unify(&t1, &t2).is_ok() {
+            return 0;
+        }
+        match (&t1, &t2) {
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                if n1 == n2 && a1.len() == a2.len() =>
+            {
+                let distance: usize = a1
+                    .iter()
+                    .zip(a2)
+                    .map(|(t1, t2)| self.unify_distance_internal(t1, t2))
+                    .sum();
+                // the arguments may each unify while the whole doesn't, e.g. with spreads.
+                distance.max(1)
+            }
+            _ => 1,
+        }
+    }
+    /// Like [`unify`], but may affect the context even under failure. Hence, use this if you
+    /// discard the context upon failure.
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_fast(
+        &mut self,
+        mut t1: Type<N>,
+        mut t2: Type<N>,
+    ) -> Result<(), UnificationError<N>> {
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        self.unify_internal(t1, t2)
+    }
+    /// Like [`unify`], but calls `on_bind` every time a variable is bound, with the variable, the
+    /// type it is bound to, and the depth within the unified types at which the binding was made.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected, though `on_bind` may already have
+    /// been called for bindings made before the failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(@arrow[tp!(0), tp!(list(tp!(1)))]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(2)]);
+    /// let mut trace = Vec::new();
+    /// ctx.unify_traced(&t1, &t2, |v, t, depth| trace.push((v, t.to_string(), depth)))
+    ///     .expect("unifies");
+    /// assert_eq!(
+    ///     trace,
+    ///     vec![(0, "int".to_string(), 1), (2, "list(t1)".to_string(), 1)]
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_traced<F: FnMut(Variable, &Type<N>, usize)>(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        mut on_bind: F,
+    ) -> Result<(), UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        ctx.unify_internal_traced(t1, t2, 0, None, &mut |v, t, depth| {
+            on_bind(v, t, depth);
+            Ok(())
+        })?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`unify`], but fails with [`UnificationError::BindingBudgetExceeded`] if it would
+    /// create more than `max_new_bindings` bindings. Unlike
+    /// [`set_max_substitution_size`], this limits the work of a single call rather than the
+    /// size of the whole context.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    /// let t1 = tp!(@arrow[tp!(0), tp!(1), tp!(2)]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(bool), tp!(int)]);
+    ///
+    /// assert_eq!(
+    ///     ctx.unify_bounded(&t1, &t2, 2),
+    ///     Err(UnificationError::BindingBudgetExceeded(2))
+    /// );
+    /// assert!(ctx.is_empty());
+    /// ctx.unify_bounded(&t1, &t2, 3).expect("within budget");
+    /// assert_eq!(ctx.len(), 3);
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`set_max_substitution_size`]: #method.set_max_substitution_size
+    /// [`UnificationError::BindingBudgetExceeded`]: enum.UnificationError.html#variant.BindingBudgetExceeded
+    pub fn unify_bounded(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        max_new_bindings: usize,
+    ) -> Result<(), UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        let mut bindings = 0;
+        ctx.unify_internal_traced(t1, t2, 0, None, &mut |_, _, _| {
+            bindings += 1;
+            if bindings > max_new_bindings {
+                Err(UnificationError::BindingBudgetExceeded(max_new_bindings))
+            } else {
+                Ok(())
+            }
+        })?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`unify`], but consults `solver` before each variable is bound, so bindings which
+    /// break constraints beyond equality (e.g. "this type is numeric") can be rejected.
+    ///
+    /// Like [`unify`], an error, including a rejection, leaves the context unaffected, though
+    /// `solver` may already have accepted bindings made before the failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{ConstraintSolver, Context, Type, UnificationError, Variable};
+    /// /// Variables in `numeric` may only be bound to `int`, `float`, or other variables.
+    /// struct Numeric {
+    ///     numeric: Vec<Variable>,
+    /// }
+    /// impl ConstraintSolver<&'static str> for Numeric {
+    ///     fn bind(&mut self, v: Variable, t: &Type) -> Result<(), UnificationError> {
+    ///         match *t {
+    ///             _ if !self.numeric.contains(&v) => Ok(()),
+    ///             Type::Variable(w) => {
+    ///                 self.numeric.push(w);
+    ///                 Ok(())
+    ///             }
+    ///             Type::Constructed("int", _) | Type::Constructed("float", _) => Ok(()),
+    ///             _ => Err(UnificationError::Failure(Type::Variable(v), t.clone())),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut ctx = Context::default();
+    /// let mut solver = Numeric { numeric: vec![0] };
+    /// let t = tp!(@arrow[tp!(0), tp!(0)]);
+    /// assert!(ctx
+    ///     .unify_with_solver(&t, &tp!(@arrow[tp!(bool), tp!(1)]), &mut solver)
+    ///     .is_err());
+    /// ctx.unify_with_solver(&t, &tp!(@arrow[tp!(int), tp!(1)]), &mut solver)
+    ///     .expect("int is numeric");
+    /// assert_eq!(t.apply(&ctx), tp!(@arrow[tp!(int), tp!(int)]));
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_with_solver<S: ConstraintSolver<N>>(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        solver: &mut S,
+    ) -> Result<(), UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        ctx.unify_internal_traced(t1, t2, 0, None, &mut |v, t, _| solver.bind(v, t))?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`unify`], but gives up with [`UnificationError::Cancelled`] once `cancel` is set,
+    /// e.g. from another thread. The flag is checked at every step of the unification.
+    ///
+    /// Like [`unify`], an error (including cancellation) leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// # use std::sync::atomic::{AtomicBool, Ordering};
+    /// let mut ctx = Context::default();
+    /// let cancel = AtomicBool::new(false);
+    ///
+    /// let t1 = tp!(@arrow[tp!(0), tp!(int)]);
+    /// let t2 = tp!(@arrow[tp!(bool), tp!(1)]);
+    /// ctx.unify_cancellable(&t1, &t2, &cancel).expect("unifies");
+    /// assert_eq!(t1.apply(&ctx), tp!(@arrow[tp!(bool), tp!(int)]));
+    ///
+    /// let mut ctx = Context::default();
+    /// cancel.store(true, Ordering::SeqCst);
+    /// assert_eq!(
+    ///     ctx.unify_cancellable(&t1, &t2, &cancel),
+    ///     Err(UnificationError::Cancelled)
+    /// );
+    /// assert!(ctx.is_empty());
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`UnificationError::Cancelled`]: enum.UnificationError.html#variant.Cancelled
+    pub fn unify_cancellable(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        cancel: &AtomicBool,
+    ) -> Result<(), UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        ctx.unify_internal_traced(t1, t2, 0, Some(cancel), &mut |_, _, _| Ok(()))?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`unify`], but modulo the given [`Axioms`]: the arguments of a commutative
+    /// constructor are unified in either order, trying them as given first.
+    ///
+    /// This is not complete AC-unification: only the first order which unifies is kept, so
+    /// other solutions are not explored, and associativity is not handled. Each commutative
+    /// constructor may double the work of unification. Aliases are expanded as by [`unify`],
+    /// and the axioms apply within their expansions too.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Axioms, Context};
+    /// let mut axioms = Axioms::default();
+    /// axioms.add_commutative("pair");
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(list(tp!(pair(tp!(int), tp!(0)))));
+    /// let t2 = tp!(list(tp!(pair(tp!(bool), tp!(int)))));
+    /// assert!(ctx.clone().unify(&t1, &t2).is_err());
+    /// ctx.unify_modulo(&t1, &t2, &axioms).expect("unifies modulo commutativity");
+    /// assert_eq!(tp!(0).apply(&ctx), tp!(bool));
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`Axioms`]: struct.Axioms.html
+    pub fn unify_modulo(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        axioms: &Axioms<N>,
+    ) -> Result<(), UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        ctx.unify_modulo_internal(t1, t2, axioms, &mut Vec::new())?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`unify`], but where the constructors of `t1` and `t2` differ, a coercion allowed by
+    /// `coercions` from the constructor in `t1` to the one in `t2` is recorded rather than
+    /// failing. The coercions are returned in the order they were found.
+    ///
+    /// A coerced subterm is replaced as a whole, so its arguments are not unified with those of
+    /// the type it is coerced to. Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Coercion, CoercionTable, Context};
+    /// let mut coercions = CoercionTable::default();
+    /// coercions.add("int", "float");
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(@arrow[tp!(int), tp!(0)]);
+    /// let t2 = tp!(@arrow[tp!(float), tp!(bool)]);
+    /// assert_eq!(
+    ///     ctx.unify_coerce(&t1, &t2, &coercions),
+    ///     Ok(vec![Coercion { from: tp!(int), to: tp!(float) }])
+    /// );
+    /// assert_eq!(tp!(0).apply(&ctx), tp!(bool));
+    ///
+    /// // coercions only go one way
+    /// assert!(ctx.unify_coerce(&t2, &t1, &coercions).is_err());
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_coerce(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        coercions: &CoercionTable<N>,
+    ) -> Result<Vec<Coercion<N>>, UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        let mut required = Vec::new();
+        ctx.unify_coerce_internal(t1, t2, coercions, &mut required)?;
+        *self = ctx;
+        Ok(required)
+    }
+    /// Like [`unify`], but reports each step of the unification as a [`UnifyEvent`], in the
+    /// order the steps were taken.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnifyEvent};
+    /// let mut ctx = Context::default();
+    ///
+    /// let t1 = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(list(tp!(bool)))]);
+    /// let events = ctx.unify_events(&t1, &t2).expect("unifies");
+    /// assert_eq!(
+    ///     events,
+    ///     vec![
+    ///         UnifyEvent::Descend(0),
+    ///         UnifyEvent::AlreadyEqual,
+    ///         UnifyEvent::Ascend,
+    ///         UnifyEvent::Descend(1),
+    ///         UnifyEvent::Descend(0),
+    ///         UnifyEvent::Bound(0, tp!(bool)),
+    ///         UnifyEvent::Ascend,
+    ///         UnifyEvent::Ascend,
+    ///     ]
+    /// );
+    ///
+    /// // an alias is expanded, then unified like any other constructed type.
+    /// ctx.add_alias("dict", tp!(list(tp!(pair(tp!(str), tp!(0))))));
+    /// let events = ctx
+    ///     .unify_events(&tp!(dict(tp!(int))), &tp!(list(tp!(pair(tp!(str), tp!(1))))))
+    ///     .expect("unifies");
+    /// assert_eq!(
+    ///     events,
+    ///     vec![
+    ///         UnifyEvent::Descend(0),
+    ///         UnifyEvent::Descend(0),
+    ///         UnifyEvent::AlreadyEqual,
+    ///         UnifyEvent::Ascend,
+    ///         UnifyEvent::Descend(1),
+    ///         UnifyEvent::Bound(1, tp!(int)),
+    ///         UnifyEvent::Ascend,
+    ///         UnifyEvent::Ascend,
+    ///     ]
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`UnifyEvent`]: enum.UnifyEvent.html
+    pub fn unify_events(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<Vec<UnifyEvent<N>>, UnificationError<N>> {
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        let mut ctx = self.clone();
+        let mut events = Vec::new();
+        ctx.unify_events_internal(t1, t2, &mut events)?;
+        *self = ctx;
+        Ok(events)
+    }
+    /// Like [`unify`], but works on a copy of the context made in `scratch`, reusing its
+    /// allocations rather than cloning the context anew. On success, the context and `scratch`
+    /// are swapped, so the old context becomes the scratch space for the next call.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected. The contents of `scratch` are
+    /// meaningless afterwards either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let mut scratch = Context::default();
+    ///
+    /// ctx.unify_with_scratch(&mut scratch, &tp!(0), &tp!(list(tp!(1))))
+    ///     .expect("unifies");
+    /// ctx.unify_with_scratch(&mut scratch, &tp!(1), &tp!(int))
+    ///     .expect("unifies");
+    /// assert!(ctx
+    ///     .unify_with_scratch(&mut scratch, &tp!(0), &tp!(int))
+    ///     .is_err());
+    /// assert_eq!(tp!(0).apply(&ctx).apply(&ctx), tp!(list(tp!(int))));
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_with_scratch(
+        &mut self,
+        scratch: &mut Context<N>,
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<(), UnificationError<N>> {
+        if self.mismatch(t1, t2) {
+            return Err(self.failure(t1.apply(self), t2.apply(self)));
+        }
+        let mut t1 = t1.clone();
+        let mut t2 = t2.clone();
+        t1.apply_mut(self);
+        t2.apply_mut(self);
+        // destructure fully so a new field cannot be silently left out of the copy.
+        let Context {
+            ref substitution,
+            next,
+            ref rigid,
+            ref labels,
+            max_substitution_size,
+            ref dynamic,
+            ref aliases,
+            ref never,
+        } = *self;
+        scratch.substitution.clone_from(substitution);
+        scratch.next = next;
+        scratch.rigid.clone_from(rigid);
+        scratch.labels.clone_from(labels);
+        scratch.max_substitution_size = max_substitution_size;
+        scratch.dynamic.clone_from(dynamic);
+        scratch.aliases.clone_from(aliases);
+        scratch.never.clone_from(never);
+        scratch.unify_internal(t1, t2)?;
+        ::std::mem::swap(self, scratch);
+        Ok(())
+    }
+    /// Like [`unify`], but returns the bindings it made, in order. These can be given to
+    /// [`replay`] to repeat the unification on an equivalent context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let bindings = ctx
+    ///     .unify_record(&tp!(list(tp!(0))), &tp!(list(tp!(int))))
+    ///     .expect("unifies");
+    /// assert_eq!(bindings, vec![(0, tp!(int))]);
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`replay`]: #method.replay
+    pub fn unify_record(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<Vec<(Variable, Type<N>)>, UnificationError<N>> {
+        let mut bindings = Vec::new();
+        self.unify_traced(t1, t2, |v, t, _| bindings.push((v, t.clone())))?;
+        Ok(bindings)
+    }
+    /// Extend the context with each of `bindings`, as recorded by [`unify_record`].
+    ///
+    /// No occurs checks or matching is done, so this is only sound on a context equivalent to
+    /// the one the bindings were recorded from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let t1 = tp!(@arrow[tp!(0), tp!(1)]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    ///
+    /// let mut fresh = ctx.clone();
+    /// let bindings = ctx.unify_record(&t1, &t2).expect("unifies");
+    /// fresh.replay(&bindings);
+    /// assert_eq!(fresh, ctx);
+    /// # }
+    /// ```
+    ///
+    /// [`unify_record`]: #method.unify_record
+    pub fn replay(&mut self, bindings: &[(Variable, Type<N>)]) {
+        for (v, t) in bindings {
+            self.extend(*v, t.clone());
+        }
+    }
+    /// Like [`unify`], but tags each binding it makes with `label`. If a later labeled
+    /// unification fails because of a variable bound this way, the error is a
+    /// [`UnificationError::FailureWithCause`] naming the variable and its label.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    /// ctx.unify_labeled(&tp!(0), &tp!(list(tp!(int))), "argument of f")
+    ///     .expect("unifies");
+    ///
+    /// let err = ctx
+    ///     .unify_labeled(&tp!(0), &tp!(list(tp!(bool))), "return of g")
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     err,
+    ///     UnificationError::FailureWithCause {
+    ///         left: tp!(int),
+    ///         right: tp!(bool),
+    ///         variable: 0,
+    ///         cause: "argument of f".to_string(),
+    ///     }
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`UnificationError::FailureWithCause`]: enum.UnificationError.html#variant.FailureWithCause
+    pub fn unify_labeled(
+        &mut self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        label: &str,
+    ) -> Result<(), UnificationError<N>> {
+        let mut bound = Vec::new();
+        match self.unify_traced(t1, t2, |v, _, _| bound.push(v)) {
+            Ok(()) => {
+                for v in bound {
+                    self.labels.insert(v, label.to_string());
+                }
+                Ok(())
+            }
+            Err(UnificationError::Failure(left, right)) => {
+                Err(match self.cause_of(t1, t2, &left, &right) {
+                    Some((variable, cause)) => UnificationError::FailureWithCause {
+                        left,
+                        right,
+                        variable,
+                        cause,
+                    },
+                    None => UnificationError::Failure(left, right),
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+    /// Find the labeled variable nearest to `t1` and `t2`, following the substitution, whose
+    /// binding contains either side of a clash.
+    fn cause_of(
+        &self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+        left: &Type<N>,
+        right: &Type<N>,
+    ) -> Option<(Variable, String)> {
+        let mut pending: VecDeque<Variable> = t1.vars().into_iter().chain(t2.vars()).collect();
+        let mut seen = HashSet::new();
+        while let Some(v) = pending.pop_front() {
+            if !seen.insert(v) {
+                continue;
+            }
+            if let Some(t) = self.substitution.get(&v) {
+                if let Some(label) = self.labels.get(&v) {
+                    let t = self.resolve(t);
+                    if has_subterm(&t, left) || has_subterm(&t, right) {
+                        return Some((v, label.clone()));
+                    }
+                }
+                pending.extend(t.vars());
+            }
+        }
+        None
+    }
+    /// If unifying `t1` and `t2` fails with [`UnificationError::Occurs`], find the binding which
+    /// failed the occurs check, so it can be reported with the type the variable would have
+    /// been bound to. This doesn't affect the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, OccursInfo};
+    /// let ctx = Context::default();
+    ///
+    /// let t1 = tp!(@arrow[tp!(0), tp!(1)]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(list(tp!(1)))]);
+    /// let info = ctx.occurs_info(&t1, &t2).expect("fails occurs check");
+    /// assert_eq!(
+    ///     info,
+    ///     OccursInfo {
+    ///         variable: 1,
+    ///         in_type: tp!(list(tp!(1))),
+    ///     }
+    /// );
+    /// assert_eq!(info.to_string(), "t1 would be bound to list(t1)");
+    ///
+    /// assert_eq!(ctx.occurs_info(&tp!(0), &tp!(int)), None);
+    /// # }
+    /// ```
+    ///
+    /// [`UnificationError::Occurs`]: enum.UnificationError.html#variant.Occurs
+    pub fn occurs_info(&self, t1: &Type<N>, t2: &Type<N>) -> Option<OccursInfo<N>> {
+        let mut ctx = self.clone();
+        match ctx.unify_internal(t1.apply(self), t2.apply(self)) {
+            // the failed context still holds the bindings made before the occurs check.
+            Err(UnificationError::Occurs(variable)) => {
+                occurs_site(&ctx.resolve(t1), &ctx.resolve(t2), variable)
+                    .map(|in_type| OccursInfo { variable, in_type })
+            }
+            _ => None,
+        }
+    }
+    /// Instantiate both schemas with disjoint fresh variables and unify the resulting types.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let s1 = ptp!(0; @arrow[tp!(0), tp!(int)]); // ∀α. α → int
+    /// let s2 = ptp!(0; @arrow[tp!(bool), tp!(0)]); // ∀α. bool → α
+    /// ctx.unify_schemas(&s1, &s2).expect("both are instances of bool → int");
+    ///
+    /// let s3 = ptp!(0; @arrow[tp!(0), tp!(0)]); // ∀α. α → α
+    /// let s4 = ptp!(@arrow[tp!(bool), tp!(int)]);
+    /// assert!(ctx.unify_schemas(&s3, &s4).is_err());
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_schemas(
+        &mut self,
+        s1: &TypeSchema<N>,
+        s2: &TypeSchema<N>,
+    ) -> Result<(), UnificationError<N>> {
+        let mut ctx = self.clone();
+        let t1 = s1.instantiate(&mut ctx);
+        let t2 = s2.instantiate(&mut ctx);
+        ctx.unify_fast(t1, t2)?;
+        *self = ctx;
+        Ok(())
+    }
+    /// Instantiate `schema` with fresh variables and unify it with `t`, returning the unified
+    /// type.
+    ///
+    /// Like [`unify`], an error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    ///
+    /// let map = ptp!(0, 1; @arrow[
+    ///     tp!(@arrow[tp!(0), tp!(1)]),
+    ///     tp!(list(tp!(0))),
+    ///     tp!(list(tp!(1))),
+    /// ]);
+    /// let t = tp!(@arrow[tp!(@arrow[tp!(int), tp!(bool)]), tp!(5)]);
+    /// let unified = ctx.unify_poly(&map, &t).expect("unifies");
+    /// assert_eq!(unified.to_string(), "(int → bool) → list(int) → list(bool)");
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    pub fn unify_poly(
+        &mut self,
+        schema: &TypeSchema<N>,
+        t: &Type<N>,
+    ) -> Result<Type<N>, UnificationError<N>> {
+        let mut ctx = self.clone();
+        let instance = schema.instantiate(&mut ctx);
+        ctx.unify_fast(instance.clone(), t.clone())?;
+        let unified = ctx.resolve(&instance);
+        *self = ctx;
+        Ok(unified)
+    }
+    /// Like [`unify`], but rather than updating the context (which requires copying it), the
+    /// new bindings are kept in an [`Overlay`]. The context is only updated if the overlay is
+    /// given to [`commit`], so a speculative unification is discarded by dropping it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.unify(&tp!(0), &tp!(int)).unwrap();
+    ///
+    /// let t1 = tp!(@arrow[tp!(0), tp!(1)]);
+    /// let t2 = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// let overlay = ctx.unify_overlay(&t1, &t2).expect("unifies");
+    /// assert_eq!(overlay.bindings().len(), 1);
+    /// assert_eq!(tp!(1).apply(&ctx), tp!(1));
+    ///
+    /// let mut expected = ctx.clone();
+    /// expected.unify(&t1, &t2).unwrap();
+    /// ctx.commit(overlay);
+    /// assert_eq!(ctx, expected);
+    /// # }
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`Overlay`]: struct.Overlay.html
+    /// [`commit`]: #method.commit
+    pub fn unify_overlay(
+        &self,
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<Overlay<N>, UnificationError<N>> {
+        let mut overlay = OverlayStore {
+            ctx: self,
+            bindings: HashMap::new(),
+        };
+        overlay.unify_internal_traced(
+            t1.apply(self),
+            t2.apply(self),
+            0,
+            None,
+            &mut |_, _, _| Ok(()),
+        )?;
+        Ok(Overlay {
+            bindings: overlay.bindings,
+        })
+    }
+    /// Add the bindings of an [`Overlay`] to the context.
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    pub fn commit(&mut self, overlay: Overlay<N>) {
+        for (v, t) in overlay.bindings {
+            self.extend(v, t);
+        }
+    }
+    /// unify_internal may mutate the context even with an error. The context on
+    /// which it's called should be discarded if there's an error.
+    fn unify_internal(&mut self, t1: Type<N>, t2: Type<N>) -> Result<(), UnificationError<N>> {
+        self.unify_internal_traced(t1, t2, 0, None, &mut |_, _, _| Ok(()))
+    }
+    /// Like unify_internal, the context should be discarded if there's an error. Each binding
+    /// is logged with the binding it replaced, so that a failed order can be undone.
+    fn unify_modulo_internal(
+        &mut self,
+        t1: Type<N>,
+        t2: Type<N>,
+        axioms: &Axioms<N>,
+        log: &mut Vec<(Variable, Option<Type<N>>)>,
+    ) -> Result<(), UnificationError<N>> {
+        if self.is_dynamic(&t1) || self.is_dynamic(&t2) {
+            return Ok(());
+        }
+        if let Some((t1, t2)) = self.expand_mismatch(&t1, &t2) {
+            // expand here, so that the axioms still apply within the alias.
+            return self.unify_modulo_internal(t1, t2, axioms, log);
+        }
+        match (t1, t2) {
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                if n1 == n2 && a1.len() == a2.len() && !has_spread(&a1) && !has_spread(&a2) =>
+            {
+                if a1.len() == 2 && axioms.is_commutative(&n1) {
+                    let (next, mark) = (self.next, log.len());
+                    let straight =
+                        self.unify_modulo_pairs(a1.iter().cloned().zip(a2.clone()), axioms, log);
+                    if straight.is_ok() {
+                        return Ok(());
+                    }
+                    self.undo(log, mark, next);
+                    let swapped = a1.into_iter().zip(a2.into_iter().rev());
+                    self.unify_modulo_pairs(swapped, axioms, log).or(straight)
+                } else {
+                    self.unify_modulo_pairs(a1.into_iter().zip(a2), axioms, log)
+                }
+            }
+            (t1, t2) => LoggedStore { ctx: self, log }.unify_internal_traced(
+                t1,
+                t2,
+                0,
+                None,
+                &mut |_, _, _| Ok(()),
+            ),
+        }
+    }
+    fn unify_modulo_pairs<I>(
+        &mut self,
+        pairs: I,
+        axioms: &Axioms<N>,
+        log: &mut Vec<(Variable, Option<Type<N>>)>,
+    ) -> Result<(), UnificationError<N>>
+    where
+        I: IntoIterator<Item = (Type<N>, Type<N>)>,
+    {
+        for (mut t1, mut t2) in pairs {
+            t1.apply_mut(self);
+            t2.apply_mut(self);
+            self.unify_modulo_internal(t1, t2, axioms, log)?;
+        }
+        Ok(())
+    }
+    /// Undo the bindings logged since `mark`, and restore the next fresh variable.
+    fn undo(&mut self, log: &mut Vec<(Variable, Option<Type<N>>)>, mark: usize, next: Variable) {
+        for (v, replaced) in log.drain(mark..).rev() {
+            match replaced {
+                Some(t) => self.substitution.insert(v, t),
+                None => self.substitution.remove(&v),
+            };
+        }
+        self.next = next;
+    }
+    /// Like unify_internal, the context should be discarded if there's an error.
+    fn unify_coerce_internal(
+        &mut self,
+        t1: Type<N>,
+        t2: Type<N>,
+        coercions: &CoercionTable<N>,
+        required: &mut Vec<Coercion<N>>,
+    ) -> Result<(), UnificationError<N>> {
+        match (t1, t2) {
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                if n1 == n2
+                    && !self.dynamic.contains(&n1)
+                    && a1.len() == a2.len()
+                    && !has_spread(&a1)
+                    && !has_spread(&a2) =>
+            {
+                for (mut t1, mut t2) in a1.into_iter().zip(a2) {
+                    t1.apply_mut(self);
+                    t2.apply_mut(self);
+                    self.unify_coerce_internal(t1, t2, coercions, required)?;
+                }
+                Ok(())
+            }
+            (t1, t2) => {
+                let coercible = match (&t1, &t2) {
+                    (Type::Constructed(n1, _), Type::Constructed(n2, _)) => {
+                        n1 != n2 && self.mismatch(&t1, &t2) && coercions.allows(n1, n2)
+                    }
+                    _ => false,
+                };
+                if coercible {
+                    required.push(Coercion { from: t1, to: t2 });
+                    Ok(())
+                } else {
+                    self.unify_internal(t1, t2)
+                }
+            }
+        }
+    }
+    /// Like unify_internal, the context should be discarded if there's an error.
+    fn unify_events_internal(
+        &mut self,
+        t1: Type<N>,
+        t2: Type<N>,
+        events: &mut Vec<UnifyEvent<N>>,
+    ) -> Result<(), UnificationError<N>> {
+        if t1 == t2 {
+            events.push(UnifyEvent::AlreadyEqual);
+            return Ok(());
+        }
+        if !self.is_dynamic(&t1) && !self.is_dynamic(&t2) {
+            if let Some((t1, t2)) = self.expand_mismatch(&t1, &t2) {
+                // expand here, so that the steps within the alias are reported too.
+                return self.unify_events_internal(t1, t2, events);
+            }
+        }
+        match (t1, t2) {
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                if n1 == n2
+                    && !self.dynamic.contains(&n1)
+                    && a1.len() == a2.len()
+                    && !has_spread(&a1)
+                    && !has_spread(&a2) =>
+            {
+                for (i, (mut t1, mut t2)) in a1.into_iter().zip(a2).enumerate() {
+                    events.push(UnifyEvent::Descend(i));
+                    t1.apply_mut(self);
+                    t2.apply_mut(self);
+                    self.unify_events_internal(t1, t2, events)?;
+                    events.push(UnifyEvent::Ascend);
+                }
+                Ok(())
+            }
+            (t1, t2) => self.unify_internal_traced(t1, t2, 0, None, &mut |v, t, _| {
+                events.push(UnifyEvent::Bound(v, t.clone()));
+                Ok(())
+            }),
+        }
+    }
+    /// Like [`Type::apply`], but bindings in `overlay` take precedence over the context.
+    ///
+    /// [`Type::apply`]: enum.Type.html#method.apply
+    fn apply_overlay(&self, t: &Type<N>, overlay: &HashMap<Variable, Type<N>>) -> Type<N> {
+        match *t {
+            Type::Constructed(ref name, ref args) => {
+                let args = args
+                    .iter()
+                    .map(|t| self.apply_overlay(t, overlay))
+                    .collect();
+                Type::Constructed(name.clone(), args)
+            }
+            Type::Variable(v) => overlay
+                .get(&v)
+                .or_else(|| self.substitution.get(&v))
+                .cloned()
+                .unwrap_or_else(|| Type::Variable(v)),
+        }
+    }
+    /// Applies the context to each of `types` in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.unify(&tp!(0), &tp!(int)).unwrap();
+    ///
+    /// let mut types = vec![tp!(0), tp!(list(tp!(0))), tp!(1)];
+    /// ctx.apply_all(&mut types);
+    /// assert_eq!(types, vec![tp!(int), tp!(list(tp!(int))), tp!(1)]);
+    /// # }
+    /// ```
+    pub fn apply_all(&self, types: &mut [Type<N>]) {
+        for tp in types {
+            tp.apply_mut(self);
+        }
+    }
+    /// Like [`apply_all`], but takes ownership of the types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.unify(&tp!(0), &tp!(int)).unwrap();
+    ///
+    /// let types = ctx.apply_all_owned(vec![tp!(0), tp!(list(tp!(0)))]);
+    /// assert_eq!(types, vec![tp!(int), tp!(list(tp!(int)))]);
+    /// # }
+    /// ```
+    ///
+    /// [`apply_all`]: #method.apply_all
+    pub fn apply_all_owned(&self, mut types: Vec<Type<N>>) -> Vec<Type<N>> {
+        self.apply_all(&mut types);
+        types
+    }
+    /// Confines the substitution to those which act on the given variables.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let v0 = ctx.new_variable();
+    /// let v1 = ctx.new_variable();
+    /// ctx.unify(&v0, &tp!(int));
+    /// ctx.unify(&v1, &tp!(bool));
+    ///
+    /// {
+    ///     let sub = ctx.substitution();
+    ///     assert_eq!(sub.len(), 2);
+    ///     assert_eq!(sub[&0], tp!(int));
+    ///     assert_eq!(sub[&1], tp!(bool));
+    /// }
+    ///
+    /// // confine the substitution to v1
+    /// ctx.confine(&[1]);
+    /// let sub = ctx.substitution();
+    /// assert_eq!(sub.len(), 1);
+    /// assert_eq!(sub[&1], tp!(bool));
+    /// # }
+    /// ```
+    pub fn confine(&mut self, keep: &[Variable]) {
+        let mut substitution = HashMap::new();
+        for v in keep {
+            substitution.insert(*v, self.substitution[v].clone());
+        }
+        self.substitution = substitution;
+    }
+    /// Bind every variable allocated by the context which is still unbound to `default`, so
+    /// applying the context grounds any type built from those variables. Skolems (see
+    /// [`new_skolem`]) are left alone.
+    ///
+    /// Each binding is made as with [`extend_checked`], so this fails if it would exceed the
+    /// limit set by [`set_max_substitution_size`], or if `default` mentions a variable it would
+    /// bind, making the substitution cyclic. An error leaves the context unaffected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, UnificationError};
+    /// let mut ctx = Context::default();
+    /// let t0 = ctx.new_variable();
+    /// let t1 = ctx.new_variable();
+    /// let t = tp!(@arrow[tp!(list(t0.clone())), t1.clone()]);
+    /// ctx.unify(&t1, &tp!(int)).expect("unifies");
+    ///
+    /// let mut limited = ctx.clone();
+    /// limited.set_max_substitution_size(Some(1));
+    /// assert_eq!(
+    ///     limited.default_unbound(&tp!(unit)),
+    ///     Err(UnificationError::SubstitutionLimitExceeded(1))
+    /// );
+    ///
+    /// ctx.default_unbound(&tp!(unit)).expect("within the limit");
+    /// assert_eq!(t.apply(&ctx), tp!(@arrow[tp!(list(tp!(unit))), tp!(int)]));
+    /// # }
+    /// ```
+    ///
+    /// [`new_skolem`]: #method.new_skolem
+    /// [`extend_checked`]: #method.extend_checked
+    /// [`set_max_substitution_size`]: #method.set_max_substitution_size
+    pub fn default_unbound(&mut self, default: &Type<N>) -> Result<(), UnificationError<N>> {
+        let mut ctx = self.clone();
+        for v in 0..self.next {
+            if !self.substitution.contains_key(&v) && !self.rigid.contains(&v) {
+                ctx.extend_checked(v, default.clone())?;
+            }
+        }
+        *self = ctx;
+        Ok(())
+    }
+    /// Like [`confine`], but keeps the substitutions reachable from variables in `roots`,
+    /// following the variables in each substituted type, and drops the rest. Unreachable
+    /// variables also lose their labels and stop being skolems.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(list(tp!(1))));
+    /// ctx.extend(1, tp!(int));
+    /// ctx.extend(2, tp!(bool));
+    ///
+    /// let live = tp!(@arrow[tp!(0), tp!(3)]);
+    /// ctx.gc(&[&live]);
+    /// let sub = ctx.substitution();
+    /// assert_eq!(sub.len(), 2);
+    /// assert_eq!(sub[&0], tp!(list(tp!(1))));
+    /// assert_eq!(sub[&1], tp!(int));
+    ///
+    /// assert_eq!(ctx.new_skolem(), tp!(3));
+    /// ctx.gc(&[&tp!(0)]);
+    /// assert!(!ctx.is_rigid(3));
+    /// # }
+    /// ```
+    ///
+    /// [`confine`]: #method.confine
+    pub fn gc(&mut self, roots: &[&Type<N>]) {
+        let reachable = self.reachable(roots);
+        self.substitution.retain(|v, _| reachable.contains(v));
+        self.labels.retain(|v, _| reachable.contains(v));
+        self.rigid.retain(|v| reachable.contains(v));
+    }
+    /// Like [`gc`] with the single root `root`, but returns a new context rather than changing
+    /// this one. Only the substitution is restricted, so unification in the result behaves as
+    /// it would in this context, with the same aliases, skolems and other settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(list(tp!(1))));
+    /// ctx.extend(1, tp!(int));
+    /// ctx.extend(2, tp!(bool));
+    ///
+    /// let root = tp!(@arrow[tp!(0), tp!(3)]);
+    /// let mut slice = ctx.slice_for(&root);
+    /// assert_eq!(slice.len(), 2);
+    /// assert_eq!(root.apply(&slice), root.apply(&ctx));
+    /// assert_eq!(slice.new_variable(), ctx.new_variable());
+    ///
+    /// ctx.add_alias("string", tp!(list(tp!(char))));
+    /// let mut slice = ctx.slice_for(&root);
+    /// slice.unify(&tp!(string), &tp!(list(tp!(char)))).expect("aliases are kept");
+    /// # }
+    /// ```
+    ///
+    /// [`gc`]: #method.gc
+    pub fn slice_for(&self, root: &Type<N>) -> Context<N> {
+        let reachable = self.reachable(&[root]);
+        let substitution = self
+            .substitution
+            .iter()
+            .filter(|&(v, _)| reachable.contains(v))
+            .map(|(v, t)| (*v, t.clone()))
+            .collect();
+        Context {
+            substitution,
+            next: self.next,
+            rigid: self.rigid.clone(),
+            labels: self.labels.clone(),
+            max_substitution_size: self.max_substitution_size,
+            dynamic: self.dynamic.clone(),
+            aliases: self.aliases.clone(),
+            never: self.never.clone(),
+        }
+    }
+    /// The variables of `roots`, and of every substituted type reachable from them.
+    fn reachable(&self, roots: &[&Type<N>]) -> HashSet<Variable> {
+        let mut pending: Vec<Variable> = roots.iter().flat_map(|t| t.vars()).collect();
+        let mut reachable = HashSet::new();
+        while let Some(v) = pending.pop() {
+            if reachable.insert(v) {
+                if let Some(t) = self.substitution.get(&v) {
+                    pending.extend(t.vars());
+                }
+            }
+        }
+        reachable
+    }
+    /// Merge two type contexts.
+    ///
+    /// Every [`Type`] ([`TypeSchema`]) that corresponds to the `other` context
+    /// must be reified using [`ContextChange::reify_type`]
+    /// ([`ContextChange::reify_typeschema`]). Any [`Variable`] in `sacreds`
+    /// will not be changed by the context (i.e. reification will ignore it).
+    ///
+    /// # Examples
+    ///
+    /// Without sacred variables, which assumes that all type variables between the contexts are
+    /// distinct:
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::{Type, Context};
+    /// # fn main() {
+    /// let mut ctx = Context::default();
+    /// let a = ctx.new_variable();
+    /// let b = ctx.new_variable();
+    /// ctx.unify(&Type::arrow(a, b), &tp!(@arrow[tp!(int), tp!(bool)])).unwrap();
+    /// // ctx uses t0 and t1
+    ///
+    /// let mut ctx2 = Context::default();
+    /// let pt = ptp!(0, 1; @arrow[tp!(0), tp!(1)]);
+    /// let mut t = pt.instantiate(&mut ctx2);
+    /// ctx2.extend(0, tp!(bool));
+    /// assert_eq!(t.apply(&ctx2).to_string(), "bool → t1");
+    /// // ctx2 uses t0 and t1
+    ///
+    /// let ctx_change = ctx.merge(ctx2, vec![]);
+    /// // rewrite all terms under ctx2 using ctx_change
+    /// ctx_change.reify_type(&mut t);
+    /// assert_eq!(t.to_string(), "t2 → t3");
+    /// assert_eq!(t.apply(&ctx).to_string(), "bool → t3");
+    ///
+    /// assert_eq!(ctx.new_variable(), tp!(4));
+    /// # }
+    /// ```
+    ///
+    /// With sacred variables, which specifies which type variables are equivalent in both
+    /// contexts:
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::{Type, Context};
+    /// # fn main() {
+    /// let mut ctx = Context::default();
+    /// let a = ctx.new_variable();
+    /// let b = ctx.new_variable();
+    /// ctx.unify(&Type::arrow(a, b), &tp!(@arrow[tp!(int), tp!(bool)])).unwrap();
+    /// // ctx uses t0 and t1
+    ///
+    /// let mut ctx2 = Context::default();
+    /// let a = ctx2.new_variable();
+    /// let b = ctx2.new_variable();
+    /// let mut t = Type::arrow(a, b);
+    /// ctx2.extend(0, tp!(bool));
+    /// assert_eq!(t.apply(&ctx2).to_string(), "bool → t1");
+    /// // ctx2 uses t0 and t1
+    ///
+    /// // t1 from ctx2 is preserved *and* constrained by ctx
+    /// let ctx_change = ctx.merge(ctx2, vec![1]);
+    /// // rewrite all terms under ctx2 using ctx_change
+    /// ctx_change.reify_type(&mut t);
+    /// assert_eq!(t.to_string(), "t2 → t1");
+    /// assert_eq!(t.apply(&ctx).to_string(), "bool → bool");
+    ///
+    /// assert_eq!(ctx.new_variable(), tp!(4));
+    /// # }
+    /// ```
+    /// [`ContextChange::reify_type`]: struct.ContextChange.html#method.reify_type
+    /// [`ContextChange::reify_typeschema`]: struct.ContextChange.html#method.reify_typeschema
+    /// [`Type`]: enum.Type.html
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`Variable`]: type.TypeSchema.html
+    pub fn merge(&mut self, other: Context<N>, sacreds: Vec<Variable>) -> ContextChange {
+        let delta = self.next;
+        for (v, tp) in other.substitution {
+            self.substitution.insert(delta + v, tp);
+        }
+        self.rigid
+            .extend(other.rigid.into_iter().map(|v| delta + v));
+        self.labels
+            .extend(other.labels.into_iter().map(|(v, l)| (delta + v, l)));
+        // this is intentionally wasting variable space when there are sacreds:
+        self.next += other.next;
+        ContextChange {
+            delta,
+            sacreds: sacreds.into_iter().map(|v| (v, v)).collect(),
+        }
+    }
+    /// Like [`merge`], but copies what it needs from `other` rather than consuming it, so the
+    /// same context can be merged into several others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Context;
+    /// # fn main() {
+    /// let mut module = Context::default();
+    /// let mut t = module.new_variable();
+    /// module.unify(&t, &tp!(int)).unwrap();
+    ///
+    /// let mut ctx1 = Context::default();
+    /// let mut ctx2 = Context::default();
+    /// ctx2.new_variable();
+    /// let change1 = ctx1.merge_ref(&module, &[]);
+    /// let change2 = ctx2.merge_ref(&module, &[]);
+    ///
+    /// let mut t2 = t.clone();
+    /// change1.reify_type(&mut t);
+    /// change2.reify_type(&mut t2);
+    /// assert_eq!(t.apply(&ctx1), tp!(int));
+    /// assert_eq!(t2.apply(&ctx2), tp!(int));
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    pub fn merge_ref(&mut self, other: &Context<N>, sacreds: &[Variable]) -> ContextChange {
+        self.merge(other.clone(), sacreds.to_vec())
+    }
+    /// Like [`merge`], but fails with [`MergeOverflow`], leaving the context unaffected, if the
+    /// shifted variables of `other` wouldn't fit in a [`Variable`] rather than wrapping around.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::{Context, MergeOverflow};
+    /// # fn main() {
+    /// let mut ctx = Context::default();
+    /// ctx.extend(60000, tp!(int));
+    ///
+    /// let mut other = Context::default();
+    /// other.extend(1, tp!(bool));
+    /// assert!(ctx.try_merge(other.clone(), &[]).is_ok());
+    ///
+    /// other.extend(10000, tp!(bool));
+    /// assert_eq!(ctx.try_merge(other, &[]).err(), Some(MergeOverflow));
+    /// assert_eq!(ctx.len(), 2);
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    /// [`MergeOverflow`]: struct.MergeOverflow.html
+    /// [`Variable`]: type.Variable.html
+    pub fn try_merge(
+        &mut self,
+        other: Context<N>,
+        sacreds: &[Variable],
+    ) -> Result<ContextChange, MergeOverflow> {
+        let max_var = other
+            .substitution
+            .iter()
+            .flat_map(|(v, tp)| tp.vars().into_iter().chain(Some(*v)))
+            .chain(other.rigid.iter().cloned())
+            .max();
+        let fits = self.next.checked_add(other.next).is_some()
+            && max_var.is_none_or(|v| self.next.checked_add(v).is_some());
+        if fits {
+            Ok(self.merge(other, sacreds.to_vec()))
+        } else {
+            Err(MergeOverflow)
+        }
+    }
+    /// Create a renumbered copy of the context whose variables start at `start`, along with
+    /// the mapping from old variables to new ones.
+    ///
+    /// Every allocated variable, and every variable mentioned by the substitution, is shifted
+    /// by `start`. This is the renaming [`merge`] performs, without actually merging. If a
+    /// shifted variable wouldn't fit in a [`Variable`], this fails with [`MergeOverflow`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Context;
+    /// # fn main() {
+    /// let mut ctx = Context::default();
+    /// let a = ctx.new_variable();
+    /// let b = ctx.new_variable();
+    /// ctx.unify(&a, &tp!(list(b))).unwrap();
+    ///
+    /// let (mut fresh, mapping) = ctx.rename_to_fresh(10).expect("fits");
+    /// assert_eq!(mapping[&0], 10);
+    /// assert_eq!(mapping[&1], 11);
+    /// assert_eq!(tp!(10).apply(&fresh).to_string(), "list(t11)");
+    /// assert_eq!(fresh.new_variable(), tp!(12));
+    ///
+    /// assert!(ctx.rename_to_fresh(65535).is_err());
+    /// # }
+    /// ```
+    ///
+    /// [`merge`]: #method.merge
+    /// [`Variable`]: type.Variable.html
+    /// [`MergeOverflow`]: struct.MergeOverflow.html
+    pub fn rename_to_fresh(
+        &self,
+        start: Variable,
+    ) -> Result<(Context<N>, HashMap<Variable, Variable>), MergeOverflow> {
+        let shift = |v: Variable| start.checked_add(v).ok_or(MergeOverflow);
+        let mut mapping: HashMap<Variable, Variable> = HashMap::new();
+        for v in 0..self.next {
+            mapping.insert(v, shift(v)?);
+        }
+        for (v, tp) in &self.substitution {
+            for w in tp.vars().into_iter().chain(Some(*v)) {
+                if let hash_map::Entry::Vacant(entry) = mapping.entry(w) {
+                    entry.insert(shift(w)?);
+                }
+            }
+        }
+        let renaming = mapping
+            .iter()
+            .map(|(&v, &w)| (v, Type::Variable(w)))
+            .collect();
+        let substitution = self
+            .substitution
+            .iter()
+            .map(|(v, tp)| (mapping[v], tp.substitute(&renaming)))
+            .collect();
+        let next = match mapping.values().max() {
+            Some(&w) => w.checked_add(1).ok_or(MergeOverflow)?,
+            None => start,
+        };
+        let rigid = self.rigid.iter().map(|v| mapping[v]).collect();
+        let labels = self
+            .labels
+            .iter()
+            .map(|(v, l)| (mapping[v], l.clone()))
+            .collect();
+        let ctx = Context {
+            substitution,
+            next,
+            rigid,
+            labels,
+            max_substitution_size: self.max_substitution_size,
+            dynamic: self.dynamic.clone(),
+            aliases: self.aliases.clone(),
+            never: self.never.clone(),
+        };
+        Ok((ctx, mapping))
+    }
+
+    /// Remove detours in substitution table
+    pub fn reduct_substitution(&mut self) {
+        let mut ret = HashMap::new();
+        for (k, v) in &self.substitution {
+            let mut v = v;
+            while let Type::Variable(k2) = v {
+                if let Some(v2) = self.substitution.get(k2) {
+                    v = v2;
+                } else {
+                    panic!("type not resolved in subst reduction")
+                }
+            }
+            ret.insert(*k, v.clone());
+        }
+        self.substitution = ret;
+    }
+    /// Like [`reduct_substitution`], but chains of variables may end in an unbound variable,
+    /// in which case each variable in the chain is bound directly to that representative.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Context;
+    /// # fn main() {
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(1));
+    /// ctx.extend(1, tp!(2));
+    /// ctx.extend(3, tp!(0));
+    /// ctx.extend(4, tp!(5));
+    /// ctx.extend(5, tp!(list(tp!(2))));
+    ///
+    /// ctx.compress_chains();
+    /// let sub = ctx.substitution();
+    /// assert_eq!(sub[&0], tp!(2));
+    /// assert_eq!(sub[&1], tp!(2));
+    /// assert_eq!(sub[&3], tp!(2));
+    /// assert_eq!(sub[&4], tp!(list(tp!(2))));
+    /// # }
+    /// ```
+    ///
+    /// [`reduct_substitution`]: #method.reduct_substitution
+    pub fn compress_chains(&mut self) {
+        let mut ret = HashMap::new();
+        for (k, v) in &self.substitution {
+            let mut v = v;
+            let mut seen = HashSet::new();
+            seen.insert(*k);
+            while let Type::Variable(k2) = *v {
+                match self.substitution.get(&k2) {
+                    Some(v2) if seen.insert(k2) => v = v2,
+                    _ => break,
+                }
+            }
+            ret.insert(*k, v.clone());
+        }
+        self.substitution = ret;
+    }
+    /// Validate the internal invariants of the context, for use in tests:
+    ///
+    /// - every variable bound or mentioned by the substitution was allocated, i.e. is less
+    ///   than the next fresh variable, so no chain of bindings dangles past it;
+    /// - the substitution is acyclic, so no variable is reachable from its own binding.
+    ///
+    /// This is only available with the `test-invariants` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, InvariantViolation};
+    /// let mut ctx = Context::default();
+    /// let t0 = ctx.new_variable();
+    /// let t1 = ctx.new_variable();
+    /// ctx.unify(&t0, &tp!(list(t1))).expect("unifies");
+    /// assert_eq!(ctx.check_invariants(), Ok(()));
+    ///
+    /// ctx.extend(1, tp!(list(tp!(5))));
+    /// assert_eq!(
+    ///     ctx.check_invariants(),
+    ///     Err(InvariantViolation::Unallocated(5))
+    /// );
+    ///
+    /// // a chain of bindings which ends at a variable the context never allocated.
+    /// let mut ctx = Context::default();
+    /// let t0 = ctx.new_variable();
+    /// let t1 = ctx.new_variable();
+    /// ctx.unify(&t0, &t1).expect("unifies");
+    /// ctx.extend(1, tp!(2));
+    /// assert_eq!(
+    ///     ctx.check_invariants(),
+    ///     Err(InvariantViolation::Unallocated(2))
+    /// );
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(1));
+    /// ctx.extend(1, tp!(list(tp!(0))));
+    /// assert!(matches!(
+    ///     ctx.check_invariants(),
+    ///     Err(InvariantViolation::Cycle(_))
+    /// ));
+    /// # }
+    /// ```
+    #[cfg(feature = "test-invariants")]
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut bound: Vec<_> = self.substitution.keys().cloned().collect();
+        bound.sort_unstable();
+        for &v in &bound {
+            let mut vars = self.substitution[&v].vars();
+            vars.sort_unstable();
+            if let Some(&w) = Some(&v).into_iter().chain(&vars).find(|&&w| w >= self.next) {
+                return Err(InvariantViolation::Unallocated(w));
+            }
+        }
+        // depth-first search, where a variable on the stack which is reached again is a cycle.
+        let mut done = HashSet::new();
+        for &root in &bound {
+            let mut stack = vec![(root, self.substitution[&root].vars())];
+            let mut on_stack: HashSet<_> = Some(root).into_iter().collect();
+            while let Some(&mut (v, ref mut pending)) = stack.last_mut() {
+                match pending.pop() {
+                    Some(w) if on_stack.contains(&w) => return Err(InvariantViolation::Cycle(w)),
+                    Some(w) if done.contains(&w) => {}
+                    Some(w) => {
+                        if let Some(t) = self.substitution.get(&w) {
+                            on_stack.insert(w);
+                            stack.push((w, t.vars()));
+                        }
+                    }
+                    None => {
+                        on_stack.remove(&v);
+                        done.insert(v);
+                        stack.pop();
                     }
-                    Ok(())
                 }
             }
         }
+        Ok(())
     }
-    /// Confines the substitution to those which act on the given variables.
+    /// Render the substitution as a [Graphviz] DOT graph, for debugging.
+    ///
+    /// There is a node for every allocated variable. Each bound variable has an edge to every
+    /// variable in the type it is bound to, labeled by the constructors enclosing that
+    /// occurrence (outermost first, separated by `/`).
     ///
     /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
-    /// # fn main() {
     /// # use polytype::Context;
+    /// # fn main() {
     /// let mut ctx = Context::default();
-    /// let v0 = ctx.new_variable();
-    /// let v1 = ctx.new_variable();
-    /// ctx.unify(&v0, &tp!(int));
-    /// ctx.unify(&v1, &tp!(bool));
-    ///
-    /// {
-    ///     let sub = ctx.substitution();
-    ///     assert_eq!(sub.len(), 2);
-    ///     assert_eq!(sub[&0], tp!(int));
-    ///     assert_eq!(sub[&1], tp!(bool));
-    /// }
+    /// let a = ctx.new_variable();
+    /// let b = ctx.new_variable();
+    /// ctx.unify(&a, &tp!(list(b))).unwrap();
     ///
-    /// // confine the substitution to v1
-    /// ctx.confine(&[1]);
-    /// let sub = ctx.substitution();
-    /// assert_eq!(sub.len(), 1);
-    /// assert_eq!(sub[&1], tp!(bool));
+    /// let dot = ctx.to_dot();
+    /// assert!(dot.starts_with("digraph substitution {"));
+    /// assert!(dot.contains("t0 [label=\"t0 := list(t1)\"];"));
+    /// assert!(dot.contains("t0 -> t1 [label=\"list\"];"));
     /// # }
     /// ```
-    pub fn confine(&mut self, keep: &[Variable]) {
-        let mut substitution = HashMap::new();
-        for v in keep {
-            substitution.insert(*v, self.substitution[v].clone());
+    ///
+    /// [Graphviz]: https://graphviz.org
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph substitution {\n");
+        for v in 0..self.next {
+            match self.substitution.get(&v) {
+                Some(tp) => dot.push_str(&format!(
+                    "    t{} [label=\"{}\"];\n",
+                    v,
+                    dot_escape(&format!("t{} := {}", v, tp))
+                )),
+                None => dot.push_str(&format!("    t{};\n", v)),
+            }
         }
-        self.substitution = substitution;
+        let mut bound: Vec<_> = self.substitution.iter().collect();
+        bound.sort_by_key(|&(v, _)| *v);
+        for (v, tp) in bound {
+            let mut edges = Vec::new();
+            dot_edges(tp, &mut Vec::new(), &mut edges);
+            for (w, label) in edges {
+                dot.push_str(&format!(
+                    "    t{} -> t{} [label=\"{}\"];\n",
+                    v,
+                    w,
+                    dot_escape(&label)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
     }
-    /// Merge two type contexts.
-    ///
-    /// Every [`Type`] ([`TypeSchema`]) that corresponds to the `other` context
-    /// must be reified using [`ContextChange::reify_type`]
-    /// ([`ContextChange::reify_typeschema`]). Any [`Variable`] in `sacreds`
-    /// will not be changed by the context (i.e. reification will ignore it).
+    /// Encode the context into a compact binary format, which is restored by [`from_bytes`].
+    /// Besides the substitution and the next fresh variable, this keeps skolems, labels, the
+    /// substitution size limit, and dynamic, alias and never types. Names are written using
+    /// [`Name::show`].
     ///
     /// # Examples
     ///
-    /// Without sacred variables, which assumes that all type variables between the contexts are
-    /// distinct:
-    ///
     /// ```
     /// # #[macro_use] extern crate polytype;
-    /// # use polytype::{Type, Context};
     /// # fn main() {
+    /// # use polytype::Context;
     /// let mut ctx = Context::default();
-    /// let a = ctx.new_variable();
-    /// let b = ctx.new_variable();
-    /// ctx.unify(&Type::arrow(a, b), &tp!(@arrow[tp!(int), tp!(bool)])).unwrap();
-    /// // ctx uses t0 and t1
-    ///
-    /// let mut ctx2 = Context::default();
-    /// let pt = ptp!(0, 1; @arrow[tp!(0), tp!(1)]);
-    /// let mut t = pt.instantiate(&mut ctx2);
-    /// ctx2.extend(0, tp!(bool));
-    /// assert_eq!(t.apply(&ctx2).to_string(), "bool → t1");
-    /// // ctx2 uses t0 and t1
-    ///
-    /// let ctx_change = ctx.merge(ctx2, vec![]);
-    /// // rewrite all terms under ctx2 using ctx_change
-    /// ctx_change.reify_type(&mut t);
-    /// assert_eq!(t.to_string(), "t2 → t3");
-    /// assert_eq!(t.apply(&ctx).to_string(), "bool → t3");
+    /// let t = ctx.new_variable();
+    /// ctx.unify(&t, &tp!(list(tp!(int)))).expect("unifies");
     ///
-    /// assert_eq!(ctx.new_variable(), tp!(4));
+    /// let bytes = ctx.to_bytes();
+    /// assert_eq!(
+    ///     bytes,
+    ///     b"\x01\x01\x00\x01\x01\x04list\x01\x00\x03int\x00\x00\x00\x00\x00\x00"
+    /// );
     /// # }
     /// ```
     ///
-    /// With sacred variables, which specifies which type variables are equivalent in both
-    /// contexts:
+    /// [`from_bytes`]: #method.from_bytes
+    /// [`Name::show`]: trait.Name.html#tymethod.show
+    pub fn to_bytes(&self) -> Vec<u8> {
+        context_to_bytes(self)
+    }
+    /// Decode a context from the format produced by [`to_bytes`]. Names are read with
+    /// [`Name::parse`]. Types nested too deeply to decode safely fail with
+    /// [`DecodeError::TooDeep`].
+    ///
+    /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
-    /// # use polytype::{Type, Context};
     /// # fn main() {
+    /// # use polytype::{Context, DecodeError};
     /// let mut ctx = Context::default();
-    /// let a = ctx.new_variable();
-    /// let b = ctx.new_variable();
-    /// ctx.unify(&Type::arrow(a, b), &tp!(@arrow[tp!(int), tp!(bool)])).unwrap();
-    /// // ctx uses t0 and t1
-    ///
-    /// let mut ctx2 = Context::default();
-    /// let a = ctx2.new_variable();
-    /// let b = ctx2.new_variable();
-    /// let mut t = Type::arrow(a, b);
-    /// ctx2.extend(0, tp!(bool));
-    /// assert_eq!(t.apply(&ctx2).to_string(), "bool → t1");
-    /// // ctx2 uses t0 and t1
+    /// let t = ctx.new_variable();
+    /// ctx.unify(&t, &tp!(@arrow[tp!(int), tp!(1)])).expect("unifies");
     ///
-    /// // t1 from ctx2 is preserved *and* constrained by ctx
-    /// let ctx_change = ctx.merge(ctx2, vec![1]);
-    /// // rewrite all terms under ctx2 using ctx_change
-    /// ctx_change.reify_type(&mut t);
-    /// assert_eq!(t.to_string(), "t2 → t1");
-    /// assert_eq!(t.apply(&ctx).to_string(), "bool → bool");
+    /// let decoded: Context = Context::from_bytes(&ctx.to_bytes()).expect("valid encoding");
+    /// assert_eq!(decoded, ctx);
     ///
-    /// assert_eq!(ctx.new_variable(), tp!(4));
+    /// let res: Result<Context, _> = Context::from_bytes(b"\x01\x01\x00");
+    /// assert_eq!(res, Err(DecodeError::UnexpectedEnd));
     /// # }
     /// ```
-    /// [`ContextChange::reify_type`]: struct.ContextChange.html#method.reify_type
-    /// [`ContextChange::reify_typeschema`]: struct.ContextChange.html#method.reify_typeschema
-    /// [`Type`]: enum.Type.html
-    /// [`TypeSchema`]: enum.TypeSchema.html
-    /// [`Variable`]: type.TypeSchema.html
-    pub fn merge(&mut self, other: Context<N>, sacreds: Vec<Variable>) -> ContextChange {
-        let delta = self.next;
-        for (v, tp) in other.substitution {
-            self.substitution.insert(delta + v, tp);
+    ///
+    /// [`to_bytes`]: #method.to_bytes
+    /// [`Name::parse`]: trait.Name.html#method.parse
+    /// [`DecodeError::TooDeep`]: enum.DecodeError.html#variant.TooDeep
+    pub fn from_bytes(bytes: &[u8]) -> Result<Context<N>, DecodeError> {
+        context_from_bytes(bytes)
+    }
+}
+
+/// Split constructor arguments into the fixed arguments and the element type of a trailing
+/// spread, if there is one. See [`Name::is_spread`].
+///
+/// [`Name::is_spread`]: trait.Name.html#method.is_spread
+fn spread_parts<N: Name>(args: &[Type<N>]) -> (&[Type<N>], Option<&Type<N>>) {
+    match args.split_last() {
+        Some((Type::Constructed(n, inner), fixed)) if n.is_spread() && inner.len() == 1 => {
+            (fixed, Some(&inner[0]))
         }
-        // this is intentionally wasting variable space when there are sacreds:
-        self.next += other.next;
-        ContextChange { delta, sacreds }
+        _ => (args, None),
     }
+}
 
-    /// Remove detours in substitution table
-    pub fn reduct_substitution(&mut self) {
-        let mut ret = HashMap::new();
-        for (k, v) in &self.substitution {
-            let mut v = v;
-            while let Type::Variable(k2) = v {
-                if let Some(v2) = self.substitution.get(&k2) {
-                    v = v2;
+fn has_spread<N: Name>(args: &[Type<N>]) -> bool {
+    spread_parts(args).1.is_some()
+}
+
+/// Where unification looks up and records bindings: a [`Context`] itself, or an overlay of new
+/// bindings on a context which stays untouched.
+///
+/// [`Context`]: struct.Context.html
+trait BindingStore<N: Name> {
+    /// The context whose skolems, dynamic types, aliases, never types and substitution limit
+    /// govern unification.
+    fn settings(&self) -> &Context<N>;
+    /// The number of substitutions, including any not yet in the context.
+    fn bound(&self) -> usize;
+    fn bind(&mut self, v: Variable, t: Type<N>);
+    /// Like [`Type::apply_mut`], with every binding of the store.
+    ///
+    /// [`Type::apply_mut`]: enum.Type.html#method.apply_mut
+    fn apply_bindings(&self, t: &mut Type<N>);
+
+    /// unify_internal_traced may bind variables even with an error. The store on which it's
+    /// called should be discarded if there's an error.
+    fn unify_internal_traced<F>(
+        &mut self,
+        t1: Type<N>,
+        t2: Type<N>,
+        depth: usize,
+        cancel: Option<&AtomicBool>,
+        on_bind: &mut F,
+    ) -> Result<(), UnificationError<N>>
+    where
+        F: FnMut(Variable, &Type<N>, usize) -> Result<(), UnificationError<N>>,
+    {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Err(UnificationError::Cancelled);
+        }
+        if t1 == t2 || self.settings().is_dynamic(&t1) || self.settings().is_dynamic(&t2) {
+            return Ok(());
+        }
+        if let Some((t1, t2)) = self.settings().expand_mismatch(&t1, &t2) {
+            return self.unify_internal_traced(t1, t2, depth, cancel, on_bind);
+        }
+        if self.settings().mismatch(&t1, &t2) {
+            return Err(self.settings().failure(t1, t2));
+        }
+        match (t1, t2) {
+            (Type::Variable(v), t2) if !self.settings().rigid.contains(&v) => {
+                if t2.occurs(v) {
+                    Err(UnificationError::Occurs(v))
                 } else {
-                    panic!("type not resolved in subst reduction")
+                    self.settings().check_limit(self.bound())?;
+                    on_bind(v, &t2, depth)?;
+                    self.bind(v, t2);
+                    Ok(())
                 }
             }
-            ret.insert(*k, v.clone());
+            (t1, Type::Variable(v)) if !self.settings().rigid.contains(&v) => {
+                if t1.occurs(v) {
+                    Err(UnificationError::Occurs(v))
+                } else {
+                    self.settings().check_limit(self.bound())?;
+                    on_bind(v, &t1, depth)?;
+                    self.bind(v, t1);
+                    Ok(())
+                }
+            }
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
+                if has_spread(&a1) || has_spread(&a2) {
+                    match spread_pairs(&a1, &a2) {
+                        Some(pairs) => self.unify_pairs(pairs, depth + 1, cancel, on_bind),
+                        None => Err(UnificationError::Failure(
+                            Type::Constructed(n1, a1),
+                            Type::Constructed(n2, a2),
+                        )),
+                    }
+                } else {
+                    self.unify_pairs(a1.into_iter().zip(a2), depth + 1, cancel, on_bind)
+                }
+            }
+            // a skolem only unifies with itself or a flexible variable.
+            (t1, t2) => Err(self.settings().failure(t1, t2)),
         }
-        self.substitution = ret;
     }
+    fn unify_pairs<I, F>(
+        &mut self,
+        pairs: I,
+        depth: usize,
+        cancel: Option<&AtomicBool>,
+        on_bind: &mut F,
+    ) -> Result<(), UnificationError<N>>
+    where
+        I: IntoIterator<Item = (Type<N>, Type<N>)>,
+        F: FnMut(Variable, &Type<N>, usize) -> Result<(), UnificationError<N>>,
+    {
+        for (mut t1, mut t2) in pairs {
+            if self.settings().mismatch(&t1, &t2) {
+                // fail before applying the bindings throughout both types.
+                return Err(self.settings().failure(t1, t2));
+            }
+            self.apply_bindings(&mut t1);
+            self.apply_bindings(&mut t2);
+            self.unify_internal_traced(t1, t2, depth, cancel, on_bind)?;
+        }
+        Ok(())
+    }
+}
+impl<N: Name> BindingStore<N> for Context<N> {
+    fn settings(&self) -> &Context<N> {
+        self
+    }
+    fn bound(&self) -> usize {
+        self.substitution.len()
+    }
+    fn bind(&mut self, v: Variable, t: Type<N>) {
+        self.extend(v, t)
+    }
+    fn apply_bindings(&self, t: &mut Type<N>) {
+        t.apply_mut(self)
+    }
+}
+/// A context which logs each binding along with the binding it replaced, so that
+/// [`Context::unify_modulo`] can undo them.
+///
+/// [`Context::unify_modulo`]: struct.Context.html#method.unify_modulo
+struct LoggedStore<'a, N: Name + 'a> {
+    ctx: &'a mut Context<N>,
+    log: &'a mut Vec<(Variable, Option<Type<N>>)>,
+}
+impl<'a, N: Name> BindingStore<N> for LoggedStore<'a, N> {
+    fn settings(&self) -> &Context<N> {
+        self.ctx
+    }
+    fn bound(&self) -> usize {
+        self.ctx.substitution.len()
+    }
+    fn bind(&mut self, v: Variable, t: Type<N>) {
+        self.log.push((v, self.ctx.substitution.get(&v).cloned()));
+        self.ctx.extend(v, t)
+    }
+    fn apply_bindings(&self, t: &mut Type<N>) {
+        t.apply_mut(self.ctx)
+    }
+}
+/// Bindings made on top of a context without changing it, for [`Context::unify_overlay`].
+///
+/// [`Context::unify_overlay`]: struct.Context.html#method.unify_overlay
+struct OverlayStore<'a, N: Name + 'a> {
+    ctx: &'a Context<N>,
+    bindings: HashMap<Variable, Type<N>>,
+}
+impl<'a, N: Name> BindingStore<N> for OverlayStore<'a, N> {
+    fn settings(&self) -> &Context<N> {
+        self.ctx
+    }
+    fn bound(&self) -> usize {
+        self.ctx.substitution.len() + self.bindings.len()
+    }
+    fn bind(&mut self, v: Variable, t: Type<N>) {
+        self.bindings.insert(v, t);
+    }
+    fn apply_bindings(&self, t: &mut Type<N>) {
+        *t = self.ctx.apply_overlay(t, &self.bindings)
+    }
+}
+
+/// Whether two types are constructed with different names or, barring spreads, different
+/// numbers of arguments. Applying a context can't change this, so unification will fail.
+fn constructors_mismatch<N: Name>(t1: &Type<N>, t2: &Type<N>) -> bool {
+    match (t1, t2) {
+        (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
+            n1 != n2 || (a1.len() != a2.len() && !has_spread(a1) && !has_spread(a2))
+        }
+        _ => false,
+    }
+}
+
+/// Pair up constructor arguments that must unify when either side ends in a spread, or `None`
+/// if there are too few arguments to cover the fixed ones.
+fn spread_pairs<N: Name>(a1: &[Type<N>], a2: &[Type<N>]) -> Option<Vec<(Type<N>, Type<N>)>> {
+    let (f1, s1) = spread_parts(a1);
+    let (f2, s2) = spread_parts(a2);
+    let n = f1.len().min(f2.len());
+    let mut pairs: Vec<_> = f1.iter().cloned().zip(f2.iter().cloned()).collect();
+    match (s1, s2) {
+        (Some(e1), Some(e2)) => {
+            pairs.extend(f2[n..].iter().map(|t| (e1.clone(), t.clone())));
+            pairs.extend(f1[n..].iter().map(|t| (t.clone(), e2.clone())));
+            pairs.push((e1.clone(), e2.clone()));
+        }
+        (Some(e1), None) if f1.len() <= f2.len() => {
+            pairs.extend(f2[n..].iter().map(|t| (e1.clone(), t.clone())));
+        }
+        (None, Some(e2)) if f2.len() <= f1.len() => {
+            pairs.extend(f1[n..].iter().map(|t| (t.clone(), e2.clone())));
+        }
+        _ => return None,
+    }
+    Some(pairs)
+}
+
+/// Collect each variable occurrence in `tp` along with the names of its enclosing constructors.
+fn dot_edges<N: Name>(tp: &Type<N>, path: &mut Vec<String>, edges: &mut Vec<(Variable, String)>) {
+    match *tp {
+        Type::Variable(v) => edges.push((v, path.join("/"))),
+        Type::Constructed(ref name, ref args) => {
+            path.push(name.show());
+            for arg in args {
+                dot_edges(arg, path, edges);
+            }
+            path.pop();
+        }
+    }
+}
+
+fn has_subterm<N: Name>(t: &Type<N>, s: &Type<N>) -> bool {
+    t == s
+        || match *t {
+            Type::Constructed(_, ref args) => args.iter().any(|t| has_subterm(t, s)),
+            Type::Variable(_) => false,
+        }
+}
+
+/// Find the first place where one side is `v` and the other is a different type containing it.
+fn occurs_site<N: Name>(t1: &Type<N>, t2: &Type<N>, v: Variable) -> Option<Type<N>> {
+    match (t1, t2) {
+        _ if t1 == t2 => None,
+        (&Type::Variable(w), t) | (t, &Type::Variable(w)) if w == v && t.occurs(v) => {
+            Some(t.clone())
+        }
+        (Type::Constructed(_, a1), Type::Constructed(_, a2)) => a1
+            .iter()
+            .zip(a2)
+            .filter_map(|(t1, t2)| occurs_site(t1, t2, v))
+            .next(),
+        _ => None,
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
 }
 
 /// Allow types to be reified for use in a different context. See [`Context::merge`].
@@ -369,7 +3406,8 @@ impl<N: Name> Context<N> {
 /// [`Context::merge`]: struct.Context.html#method.merge
 pub struct ContextChange {
     delta: u16,
-    sacreds: Vec<Variable>,
+    /// Free variables which aren't shifted by `delta`, each with the variable it becomes.
+    sacreds: Vec<(Variable, Variable)>,
 }
 impl ContextChange {
     /// Reify a [`Type`] for use under a merged [`Context`].
@@ -381,8 +3419,13 @@ impl ContextChange {
             Type::Constructed(_, args) => for arg in args {
                 self.reify_type(arg)
             },
-            Type::Variable(n) if self.sacreds.contains(n) => (),
-            Type::Variable(n) => *n += self.delta,
+            Type::Variable(n) => *n = self.reify_variable(*n),
+        }
+    }
+    fn reify_variable(&self, n: Variable) -> Variable {
+        match self.sacreds.iter().find(|&&(v, _)| v == n) {
+            Some(&(_, to)) => to,
+            None => n + self.delta,
         }
     }
     /// Reify a [`TypeSchema`] for use under a merged [`Context`].
@@ -398,4 +3441,207 @@ impl ContextChange {
             }
         }
     }
+    /// Compose two changes into one, so that reifying with the result is the same as reifying
+    /// with `self` and then with `other`, in a single pass over the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Context;
+    /// # fn main() {
+    /// let mut ctx: Context = Context::default();
+    /// ctx.new_variable();
+    /// ctx.new_variable();
+    ///
+    /// let mut other: Context = Context::default();
+    /// other.new_variable();
+    /// let first = ctx.merge(other.clone(), vec![]);
+    /// let second = ctx.merge(other, vec![2]);
+    ///
+    /// let mut t = tp!(list(tp!(0)));
+    /// let mut expected = t.clone();
+    /// first.reify_type(&mut expected);
+    /// second.reify_type(&mut expected);
+    /// assert_eq!(expected, tp!(list(tp!(2))));
+    ///
+    /// first.then(second).reify_type(&mut t);
+    /// assert_eq!(t, expected);
+    /// # }
+    /// ```
+    pub fn then(self, other: ContextChange) -> ContextChange {
+        // a sacred of self ends up wherever other takes it, and a variable which self shifts
+        // onto a sacred of other stops there, while every other variable shifts by both.
+        let mut sacreds: Vec<_> = self
+            .sacreds
+            .iter()
+            .map(|&(v, to)| (v, other.reify_variable(to)))
+            .collect();
+        for &(v, to) in &other.sacreds {
+            if v >= self.delta && self.sacreds.iter().all(|&(w, _)| w != v - self.delta) {
+                sacreds.push((v - self.delta, to));
+            }
+        }
+        ContextChange {
+            delta: self.delta + other.delta,
+            sacreds,
+        }
+    }
+}
+
+/// A [`Context`] shared between branches of a search, which is only copied when a branch
+/// changes it. Cloning a `CowContext` is cheap, and reading from it never copies.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate polytype;
+/// # fn main() {
+/// # use polytype::{Context, CowContext};
+/// let mut root = CowContext::new(Context::default());
+/// root.unify(&tp!(0), &tp!(int)).expect("unifies");
+///
+/// let mut branch = root.clone();
+/// assert_eq!(branch.apply(&tp!(list(tp!(0)))), tp!(list(tp!(int))));
+/// assert!(branch.unify(&tp!(0), &tp!(bool)).is_err());
+///
+/// branch.unify(&tp!(1), &tp!(bool)).expect("unifies");
+/// assert_eq!(branch.apply(&tp!(1)), tp!(bool));
+/// assert_eq!(root.apply(&tp!(1)), tp!(1));
+/// # }
+/// ```
+///
+/// [`Context`]: struct.Context.html
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CowContext<N: Name = &'static str> {
+    ctx: Rc<Context<N>>,
+    /// For a fork, the next variable it allocates and the end of its block, exclusive.
+    block: Option<(Variable, Variable)>,
+}
+impl<N: Name> CowContext<N> {
+    /// Share `ctx` between branches.
+    pub fn new(ctx: Context<N>) -> CowContext<N> {
+        CowContext {
+            ctx: Rc::new(ctx),
+            block: None,
+        }
+    }
+    /// Derive a branch for independent work, such as inference on another thread in a pool,
+    /// which shares this context without copying it.
+    ///
+    /// Forks allocate fresh variables only from their own block of `reserve` variables: block
+    /// `index` of those following the variables this context allocated. Forks with different
+    /// indices thus never collide, so their bindings can be brought back together with
+    /// [`Context::unify`]. This context doesn't skip over the blocks, so it shouldn't allocate
+    /// variables while its forks are in use. Fails if the block doesn't fit among the
+    /// variables left to this context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, CowContext, Type};
+    /// let mut prelude = Context::default();
+    /// let a = prelude.new_variable();
+    /// prelude.unify(&a, &tp!(int)).unwrap();
+    /// let prelude = CowContext::new(prelude);
+    ///
+    /// let mut left = prelude.fork(0, 10).expect("room for the block");
+    /// let mut right = prelude.fork(1, 10).expect("room for the block");
+    /// let l = left.new_variable();
+    /// let r = right.new_variable();
+    /// assert_eq!((l.clone(), r.clone()), (tp!(1), tp!(11)));
+    ///
+    /// left.unify(&l, &tp!(list(a.clone()))).unwrap();
+    /// right.unify(&r, &tp!(bool)).unwrap();
+    /// let mut merged = prelude.into_context();
+    /// for child in vec![left, right] {
+    ///     for (v, t) in child.context() {
+    ///         merged.unify(&Type::Variable(v), t).unwrap();
+    ///     }
+    /// }
+    /// assert_eq!(l.apply(&merged), tp!(list(tp!(int))));
+    /// assert_eq!(r.apply(&merged), tp!(bool));
+    ///
+    /// assert!(CowContext::new(merged).fork(6553, 10).is_err());
+    /// # }
+    /// ```
+    ///
+    /// [`Context::unify`]: struct.Context.html#method.unify
+    pub fn fork(&self, index: Variable, reserve: Variable) -> Result<CowContext<N>, MergeOverflow> {
+        let (next, end) = self.block.unwrap_or((self.ctx.next, Variable::MAX));
+        let start = index
+            .checked_mul(reserve)
+            .and_then(|offset| next.checked_add(offset))
+            .ok_or(MergeOverflow)?;
+        let stop = start
+            .checked_add(reserve)
+            .filter(|&stop| stop <= end)
+            .ok_or(MergeOverflow)?;
+        Ok(CowContext {
+            ctx: Rc::clone(&self.ctx),
+            block: Some((start, stop)),
+        })
+    }
+    /// The shared [`Context`].
+    ///
+    /// [`Context`]: struct.Context.html
+    pub fn context(&self) -> &Context<N> {
+        &self.ctx
+    }
+    /// The [`Context`], copied only if it is still shared with another branch.
+    ///
+    /// [`Context`]: struct.Context.html
+    pub fn into_context(self) -> Context<N> {
+        let mut ctx = Rc::try_unwrap(self.ctx).unwrap_or_else(|ctx| (*ctx).clone());
+        if let Some((next, _)) = self.block {
+            ctx.next = ctx.next.max(next);
+        }
+        ctx
+    }
+    /// Like [`Type::apply`] with the shared context.
+    ///
+    /// [`Type::apply`]: enum.Type.html#method.apply
+    pub fn apply(&self, t: &Type<N>) -> Type<N> {
+        t.apply(&self.ctx)
+    }
+    /// Like [`Context::new_variable`], copying the context first if it is shared. A fork
+    /// allocates from its block instead, without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a [`fork`] which already allocated every variable of its block.
+    ///
+    /// [`Context::new_variable`]: struct.Context.html#method.new_variable
+    /// [`fork`]: #method.fork
+    pub fn new_variable(&mut self) -> Type<N> {
+        match self.block {
+            Some((ref mut next, end)) => {
+                assert!(
+                    *next < end,
+                    "fork allocated more variables than it reserved"
+                );
+                *next += 1;
+                Type::Variable(*next - 1)
+            }
+            None => Rc::make_mut(&mut self.ctx).new_variable(),
+        }
+    }
+    /// Like [`Context::unify`]. The context is copied only if unification succeeds, and other
+    /// branches sharing it are unaffected.
+    ///
+    /// [`Context::unify`]: struct.Context.html#method.unify
+    pub fn unify(&mut self, t1: &Type<N>, t2: &Type<N>) -> Result<(), UnificationError<N>> {
+        let overlay = self.ctx.unify_overlay(t1, t2)?;
+        if !overlay.bindings.is_empty() {
+            Rc::make_mut(&mut self.ctx).commit(overlay);
+        }
+        Ok(())
+    }
+}
+impl<N: Name> From<Context<N>> for CowContext<N> {
+    fn from(ctx: Context<N>) -> CowContext<N> {
+        CowContext::new(ctx)
+    }
 }
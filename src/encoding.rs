@@ -0,0 +1,353 @@
+use std::error;
+use std::fmt;
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use {Context, Name, Type, Variable};
+
+/// Errors when decoding a [`Type`] from the format produced by [`Type::encode`], or a
+/// [`Context`] from the format produced by [`Context::to_bytes`].
+///
+/// [`Type`]: enum.Type.html
+/// [`Type::encode`]: enum.Type.html#method.encode
+/// [`Context`]: struct.Context.html
+/// [`Context::to_bytes`]: struct.Context.html#method.to_bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ended before a complete type was read.
+    UnexpectedEnd,
+    /// An unexpected character (or byte, for binary input) was found at the given byte offset.
+    UnexpectedChar(usize),
+    /// A number starting at the given byte offset was missing or out of range.
+    InvalidNumber(usize),
+    /// A name could not be parsed by [`Name::parse`].
+    ///
+    /// [`Name::parse`]: trait.Name.html#method.parse
+    InvalidName(String),
+    /// A complete type was read, but input remained from the given byte offset.
+    TrailingInput(usize),
+    /// Types were nested more deeply than the decoder allows, at the given byte offset.
+    TooDeep(usize),
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            DecodeError::UnexpectedEnd => write!(f, "UnexpectedEnd"),
+            DecodeError::UnexpectedChar(i) => write!(f, "UnexpectedChar({})", i),
+            DecodeError::InvalidNumber(i) => write!(f, "InvalidNumber({})", i),
+            DecodeError::InvalidName(ref s) => write!(f, "InvalidName({})", s),
+            DecodeError::TrailingInput(i) => write!(f, "TrailingInput({})", i),
+            DecodeError::TooDeep(i) => write!(f, "TooDeep({})", i),
+        }
+    }
+}
+impl error::Error for DecodeError {
+    fn description(&self) -> &'static str {
+        "decoding failed"
+    }
+}
+
+/// How deeply types may nest when decoding, so that corrupt input can't overflow the stack.
+const MAX_DEPTH: usize = 512;
+
+/// Variables are `v<id>`. Constructed types are `c<arity>:<length>:<name>` followed by each of
+/// their arguments, where `<length>` is the byte length of the shown name.
+pub fn encode_type<N: Name>(tp: &Type<N>, out: &mut String) {
+    match *tp {
+        Type::Variable(v) => {
+            out.push('v');
+            out.push_str(&v.to_string());
+        }
+        Type::Constructed(ref name, ref args) => {
+            let name = name.show();
+            out.push_str(&format!("c{}:{}:{}", args.len(), name.len(), name));
+            for arg in args {
+                encode_type(arg, out);
+            }
+        }
+    }
+}
+
+pub fn decode_type<N: Name>(input: &str) -> Result<Type<N>, DecodeError> {
+    let mut decoder = Decoder { input, pos: 0 };
+    let tp = decoder.tp(0)?;
+    if decoder.pos == input.len() {
+        Ok(tp)
+    } else {
+        Err(DecodeError::TrailingInput(decoder.pos))
+    }
+}
+
+struct Decoder<'a> {
+    input: &'a str,
+    pos: usize,
+}
+impl<'a> Decoder<'a> {
+    fn tp<N: Name>(&mut self, depth: usize) -> Result<Type<N>, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::TooDeep(self.pos));
+        }
+        match self.input.as_bytes().get(self.pos) {
+            Some(b'v') => {
+                self.pos += 1;
+                Ok(Type::Variable(self.number()?))
+            }
+            Some(b'c') => {
+                self.pos += 1;
+                let arity: usize = self.number()?;
+                self.expect(b':')?;
+                let len: usize = self.number()?;
+                self.expect(b':')?;
+                if len > self.input.len() - self.pos {
+                    return Err(DecodeError::UnexpectedEnd);
+                }
+                let raw = self
+                    .input
+                    .get(self.pos..self.pos + len)
+                    .ok_or(DecodeError::UnexpectedChar(self.pos))?;
+                self.pos += len;
+                let name = N::parse(raw).map_err(|_| DecodeError::InvalidName(raw.to_string()))?;
+                let mut args = Vec::new();
+                for _ in 0..arity {
+                    args.push(self.tp(depth + 1)?);
+                }
+                Ok(Type::Constructed(name, args))
+            }
+            Some(_) => Err(DecodeError::UnexpectedChar(self.pos)),
+            None => Err(DecodeError::UnexpectedEnd),
+        }
+    }
+    fn number<T: ::std::str::FromStr>(&mut self) -> Result<T, DecodeError> {
+        let start = self.pos;
+        let digits = self.input[start..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        self.pos += digits;
+        self.input[start..self.pos]
+            .parse()
+            .map_err(|_| DecodeError::InvalidNumber(start))
+    }
+    fn expect(&mut self, c: u8) -> Result<(), DecodeError> {
+        match self.input.as_bytes().get(self.pos) {
+            Some(&b) if b == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(DecodeError::UnexpectedChar(self.pos)),
+            None => Err(DecodeError::UnexpectedEnd),
+        }
+    }
+}
+
+/// A context is its `next` variable and the number of substitutions, followed by each
+/// substitution as its variable and type, in order of variable. Then come its skolems, in order,
+/// its labels, as variable and string in order of variable, its substitution limit, as `0` for
+/// none or `<limit + 1>`, its dynamic type names, its aliases as name and expansion, and its
+/// never type names, each list preceded by its length. Numbers are LEB128 varints. Variables
+/// are `0<id>`. Constructed types are `1<arity><name>` followed by each of their arguments.
+/// Names and strings are `<length><bytes>`, where `<length>` is the byte length of the shown
+/// name or the string.
+pub fn context_to_bytes<N: Name>(ctx: &Context<N>) -> Vec<u8> {
+    // destructure fully so a new field cannot be silently left out of the encoding.
+    let Context {
+        ref substitution,
+        next,
+        ref rigid,
+        ref labels,
+        max_substitution_size,
+        ref dynamic,
+        ref aliases,
+        ref never,
+    } = *ctx;
+    let mut out = Vec::new();
+    let mut substitution: Vec<_> = substitution.iter().collect();
+    substitution.sort_by_key(|&(v, _)| *v);
+    push_varint(u64::from(next), &mut out);
+    push_varint(substitution.len() as u64, &mut out);
+    for (v, tp) in substitution {
+        push_varint(u64::from(*v), &mut out);
+        push_type_bytes(tp, &mut out);
+    }
+    let mut rigid: Vec<_> = rigid.iter().collect();
+    rigid.sort();
+    push_varint(rigid.len() as u64, &mut out);
+    for v in rigid {
+        push_varint(u64::from(*v), &mut out);
+    }
+    let mut labels: Vec<_> = labels.iter().collect();
+    labels.sort_by_key(|&(v, _)| *v);
+    push_varint(labels.len() as u64, &mut out);
+    for (v, label) in labels {
+        push_varint(u64::from(*v), &mut out);
+        push_str_bytes(label, &mut out);
+    }
+    let limit = max_substitution_size.map_or(0, |max| max as u64 + 1);
+    push_varint(limit, &mut out);
+    push_varint(dynamic.len() as u64, &mut out);
+    for name in dynamic {
+        push_str_bytes(&name.show(), &mut out);
+    }
+    push_varint(aliases.len() as u64, &mut out);
+    for (name, expansion) in aliases {
+        push_str_bytes(&name.show(), &mut out);
+        push_type_bytes(expansion, &mut out);
+    }
+    push_varint(never.len() as u64, &mut out);
+    for name in never {
+        push_str_bytes(&name.show(), &mut out);
+    }
+    out
+}
+
+fn push_type_bytes<N: Name>(tp: &Type<N>, out: &mut Vec<u8>) {
+    match *tp {
+        Type::Variable(v) => {
+            out.push(0);
+            push_varint(u64::from(v), out);
+        }
+        Type::Constructed(ref name, ref args) => {
+            out.push(1);
+            push_varint(args.len() as u64, out);
+            push_str_bytes(&name.show(), out);
+            for arg in args {
+                push_type_bytes(arg, out);
+            }
+        }
+    }
+}
+
+fn push_str_bytes(s: &str, out: &mut Vec<u8>) {
+    push_varint(s.len() as u64, out);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn push_varint(mut n: u64, out: &mut Vec<u8>) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+pub fn context_from_bytes<N: Name>(input: &[u8]) -> Result<Context<N>, DecodeError> {
+    let mut decoder = ByteDecoder { input, pos: 0 };
+    let next = decoder.variable()?;
+    let mut substitution = HashMap::new();
+    for _ in 0..decoder.varint()? {
+        let v = decoder.variable()?;
+        let tp = decoder.tp(0)?;
+        substitution.insert(v, tp);
+    }
+    let mut rigid = HashSet::new();
+    for _ in 0..decoder.varint()? {
+        rigid.insert(decoder.variable()?);
+    }
+    let mut labels = HashMap::new();
+    for _ in 0..decoder.varint()? {
+        let v = decoder.variable()?;
+        labels.insert(v, decoder.string()?.to_string());
+    }
+    let start = decoder.pos;
+    let max_substitution_size = match decoder.varint()? {
+        0 => None,
+        n => Some(usize::try_from(n - 1).map_err(|_| DecodeError::InvalidNumber(start))?),
+    };
+    let mut dynamic = Vec::new();
+    for _ in 0..decoder.varint()? {
+        dynamic.push(decoder.name()?);
+    }
+    let mut aliases = Vec::new();
+    for _ in 0..decoder.varint()? {
+        let name = decoder.name()?;
+        aliases.push((name, decoder.tp(0)?));
+    }
+    let mut never = Vec::new();
+    for _ in 0..decoder.varint()? {
+        never.push(decoder.name()?);
+    }
+    if decoder.pos != input.len() {
+        return Err(DecodeError::TrailingInput(decoder.pos));
+    }
+    Ok(Context {
+        substitution,
+        next,
+        rigid,
+        labels,
+        max_substitution_size,
+        dynamic,
+        aliases,
+        never,
+    })
+}
+
+struct ByteDecoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+impl<'a> ByteDecoder<'a> {
+    fn tp<N: Name>(&mut self, depth: usize) -> Result<Type<N>, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::TooDeep(self.pos));
+        }
+        match self.input.get(self.pos) {
+            Some(0) => {
+                self.pos += 1;
+                Ok(Type::Variable(self.variable()?))
+            }
+            Some(1) => {
+                self.pos += 1;
+                let arity = self.varint()?;
+                let name = self.name()?;
+                let mut args = Vec::new();
+                for _ in 0..arity {
+                    args.push(self.tp(depth + 1)?);
+                }
+                Ok(Type::Constructed(name, args))
+            }
+            Some(_) => Err(DecodeError::UnexpectedChar(self.pos)),
+            None => Err(DecodeError::UnexpectedEnd),
+        }
+    }
+    fn name<N: Name>(&mut self) -> Result<N, DecodeError> {
+        let raw = self.string()?;
+        N::parse(raw).map_err(|_| DecodeError::InvalidName(raw.to_string()))
+    }
+    fn string(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.varint()?;
+        if ((self.input.len() - self.pos) as u64) < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let start = self.pos;
+        self.pos += len as usize;
+        ::std::str::from_utf8(&self.input[start..self.pos])
+            .map_err(|_| DecodeError::UnexpectedChar(start))
+    }
+    fn variable(&mut self) -> Result<Variable, DecodeError> {
+        let start = self.pos;
+        let n = self.varint()?;
+        if n > u64::from(Variable::MAX) {
+            Err(DecodeError::InvalidNumber(start))
+        } else {
+            Ok(n as Variable)
+        }
+    }
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.pos;
+        let mut n: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let b = *self.input.get(self.pos).ok_or(DecodeError::UnexpectedEnd)?;
+            self.pos += 1;
+            if shift >= 64 || (shift == 63 && b > 1) {
+                return Err(DecodeError::InvalidNumber(start));
+            }
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+}
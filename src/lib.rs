@@ -112,11 +112,23 @@ extern crate nom;
 #[macro_use]
 mod macros;
 mod context;
+mod encoding;
 mod parser;
 mod types;
 
-pub use context::{Context, ContextChange, UnificationError};
-pub use types::{Type, TypeSchema, Variable};
+#[cfg(feature = "test-invariants")]
+pub use context::InvariantViolation;
+pub use context::{
+    Axioms, Bindings, Coercion, CoercionTable, ConstraintSolver, Context, ContextChange,
+    CowContext, EscapeError, MergeOverflow, OccursInfo, Overlay, ScopeId, SignatureError,
+    TypeError, UnificationError, UnifyEvent,
+};
+pub use encoding::DecodeError;
+pub use parser::ParseError;
+pub use types::{
+    infer_arities, ArityConflict, Children, Cursor, LazyType, SubsumptionError, Type, TypeDiff,
+    TypeSchema, Variable,
+};
 
 /// Types require a `Name` for comparison.
 ///
@@ -162,13 +174,53 @@ pub trait Name: Clone + Eq {
     /// with [`show`].
     ///
     /// [`show`]: #method.show
-    fn parse(&str) -> Result<Self, ()> {
+    #[allow(clippy::result_unit_err)]
+    fn parse(_: &str) -> Result<Self, ()> {
         Err(())
     }
 
     fn is_arrow(&self) -> bool {
         *self == Self::arrow()
     }
+    /// Whether the name marks a spread for variadic constructors. A spread is a constructed
+    /// type with this name and exactly one argument, the element type. As the final argument
+    /// of a constructed type, it unifies with zero or more arguments of the element type.
+    ///
+    /// By default, no name is a spread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{Context, Name, Type};
+    /// #[derive(Debug, Clone, PartialEq, Eq)]
+    /// enum N { Arrow, Spread, Fn, Int }
+    /// impl Name for N {
+    ///     fn arrow() -> Self {
+    ///         N::Arrow
+    ///     }
+    ///     fn is_spread(&self) -> bool {
+    ///         *self == N::Spread
+    ///     }
+    /// }
+    ///
+    /// let tint = Type::Constructed(N::Int, vec![]);
+    /// // fn(int, ...t0)
+    /// let variadic = Type::Constructed(N::Fn, vec![
+    ///     tint.clone(),
+    ///     Type::Constructed(N::Spread, vec![Type::Variable(0)]),
+    /// ]);
+    ///
+    /// let mut ctx = Context::default();
+    /// let call = Type::Constructed(N::Fn, vec![tint.clone(), tint.clone(), tint.clone()]);
+    /// ctx.unify(&variadic, &call).expect("spread matches two arguments");
+    /// assert_eq!(Type::Variable(0).apply(&ctx), tint);
+    ///
+    /// let call = Type::Constructed(N::Fn, vec![]);
+    /// ctx.unify(&variadic, &call).expect_err("fixed argument is missing");
+    /// ```
+    fn is_spread(&self) -> bool {
+        false
+    }
 }
 impl Name for &'static str {
     /// The rightwards arrow in unicode: `→`.
@@ -1,5 +1,10 @@
+// nom's method-style macros are deprecated in favor of the nom-methods crate.
+#![allow(deprecated)]
+
 use nom::types::CompleteStr;
 use nom::{alpha, digit};
+use std::error;
+use std::fmt;
 use std::marker::PhantomData;
 use std::num::ParseIntError;
 
@@ -18,6 +23,62 @@ pub fn parse_typeschema<N: Name>(input: &str) -> Result<TypeSchema<N>, ()> {
     }
 }
 
+/// An error from parsing a [`Type`] with [`Type::parse_with`].
+///
+/// [`Type`]: enum.Type.html
+/// [`Type::parse_with`]: enum.Type.html#method.parse_with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "ParseError")
+    }
+}
+impl error::Error for ParseError {
+    fn description(&self) -> &'static str {
+        "parsing failed"
+    }
+}
+
+pub fn parse_type_with<N: Name, F: FnMut(&str) -> N>(
+    input: &str,
+    mut resolve: F,
+) -> Result<Type<N>, ParseError> {
+    match parse_type::<RawName>(input) {
+        Ok(t) => Ok(resolve_names(t, &mut resolve)),
+        Err(()) => Err(ParseError),
+    }
+}
+
+/// Constructor names as written, so they can be resolved afterward. The arrow is `None`.
+#[derive(Clone, PartialEq, Eq)]
+struct RawName(Option<String>);
+impl Name for RawName {
+    fn arrow() -> Self {
+        RawName(None)
+    }
+    fn parse(s: &str) -> Result<Self, ()> {
+        Ok(RawName(Some(s.to_string())))
+    }
+}
+
+fn resolve_names<N: Name, F: FnMut(&str) -> N>(t: Type<RawName>, resolve: &mut F) -> Type<N> {
+    match t {
+        Type::Variable(v) => Type::Variable(v),
+        Type::Constructed(RawName(name), args) => {
+            let name = match name {
+                Some(name) => resolve(&name),
+                None => N::arrow(),
+            };
+            let args = args
+                .into_iter()
+                .map(|t| resolve_names(t, resolve))
+                .collect();
+            Type::Constructed(name, args)
+        }
+    }
+}
+
 fn nom_u16(inp: CompleteStr) -> Result<u16, ParseIntError> {
     inp.parse()
 }
@@ -1,9 +1,14 @@
 use itertools::Itertools;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::error;
 use std::fmt;
+use std::hash::Hash;
+use std::rc::Rc;
 
-use parser::{parse_type, parse_typeschema};
-use {Context, Name};
+use encoding::{decode_type, encode_type};
+use parser::{parse_type, parse_type_with, parse_typeschema};
+use {Context, DecodeError, Name, ParseError};
 
 /// Represents a [type variable][1] (an unknown type).
 ///
@@ -75,6 +80,155 @@ impl<N: Name> TypeSchema<N> {
         }
         bvs
     }
+    /// Returns each quantified [`Variable`], outermost first. This is an alias for
+    /// [`bound_vars`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(3, 1; @arrow[tp!(1), tp!(3)]); // ∀α. ∀β. β → α
+    /// assert_eq!(t.bound_variables(), vec![3, 1]);
+    /// # }
+    /// ```
+    ///
+    /// [`Variable`]: type.Variable.html
+    /// [`bound_vars`]: #method.bound_vars
+    pub fn bound_variables(&self) -> Vec<Variable> {
+        self.bound_vars()
+    }
+    /// The number of quantifiers in the [`TypeSchema`], without collecting them as
+    /// [`bound_vars`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(0, 1; @arrow[tp!(1), tp!(2)]); // ∀α. ∀β. β → ɣ
+    /// assert_eq!(t.num_quantifiers(), 2);
+    /// assert_eq!(ptp!(int).num_quantifiers(), 0);
+    /// # }
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`bound_vars`]: #method.bound_vars
+    pub fn num_quantifiers(&self) -> usize {
+        let mut t = self;
+        let mut n = 0;
+        while let TypeSchema::Polytype { ref body, .. } = *t {
+            n += 1;
+            t = body
+        }
+        n
+    }
+    /// Returns the innermost [`Type`] of the [`TypeSchema`], beneath all quantifiers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(0, 1; @arrow[tp!(0), tp!(1)]); // ∀α. ∀β. α → β
+    /// assert_eq!(t.body(), &tp!(@arrow[tp!(0), tp!(1)]));
+    ///
+    /// let t = ptp!(int);
+    /// assert_eq!(t.body(), &tp!(int));
+    /// # }
+    /// ```
+    ///
+    /// [`Type`]: enum.Type.html
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    pub fn body(&self) -> &Type<N> {
+        let mut t = self;
+        loop {
+            match *t {
+                TypeSchema::Monotype(ref tp) => return tp,
+                TypeSchema::Polytype { ref body, .. } => t = body,
+            }
+        }
+    }
+    /// Quantifies `body` over each of `vars`, the first of which becomes the outermost
+    /// quantifier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::TypeSchema;
+    /// let t = TypeSchema::quantify(&[0, 1], tp!(@arrow[tp!(0), tp!(1)]));
+    /// assert_eq!(t, ptp!(0, 1; @arrow[tp!(0), tp!(1)]));
+    ///
+    /// let t = TypeSchema::quantify(&[], tp!(int));
+    /// assert_eq!(t, ptp!(int));
+    /// # }
+    /// ```
+    pub fn quantify(vars: &[Variable], body: Type<N>) -> TypeSchema<N> {
+        vars.iter()
+            .rev()
+            .fold(TypeSchema::Monotype(body), |t, &variable| {
+                TypeSchema::Polytype {
+                    variable,
+                    body: Box::new(t),
+                }
+            })
+    }
+    /// Decomposes the [`TypeSchema`] into its quantified variables, outermost first, and its
+    /// innermost [`Type`]. This is the inverse of [`quantify`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(3, 1; @arrow[tp!(1), tp!(3)]); // ∀α. ∀β. β → α
+    /// let (vars, body) = t.into_flat();
+    /// assert_eq!(vars, vec![3, 1]);
+    /// assert_eq!(body, tp!(@arrow[tp!(1), tp!(3)]));
+    /// # }
+    /// ```
+    ///
+    /// [`Type`]: enum.Type.html
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`quantify`]: #method.quantify
+    pub fn into_flat(self) -> (Vec<Variable>, Type<N>) {
+        let mut t = self;
+        let mut vars = Vec::new();
+        loop {
+            match t {
+                TypeSchema::Monotype(tp) => return (vars, tp),
+                TypeSchema::Polytype { variable, body } => {
+                    vars.push(variable);
+                    t = *body;
+                }
+            }
+        }
+    }
+    /// Applies `f` to the innermost [`Type`] of the [`TypeSchema`], keeping its quantifiers.
+    ///
+    /// Any quantified variable in the result of `f` is bound by the schema, so `f` is
+    /// responsible for using those variables as intended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Type;
+    /// let t = ptp!(0; @arrow[tp!(0), tp!(0)]); // ∀α. α → α
+    /// let t = t.map_body(|body| Type::Constructed("list", vec![body]));
+    /// assert_eq!(t.to_string(), "∀t0. list(t0 → t0)");
+    /// # }
+    /// ```
+    ///
+    /// [`Type`]: enum.Type.html
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    pub fn map_body<F: FnOnce(Type<N>) -> Type<N>>(self, f: F) -> TypeSchema<N> {
+        let (vars, body) = self.into_flat();
+        TypeSchema::quantify(&vars, f(body))
+    }
     /// Returns a set of each free [`Variable`] in the [`TypeSchema`].
     ///
     /// # Examples
@@ -104,6 +258,64 @@ impl<N: Name> TypeSchema<N> {
             }
         }
     }
+    /// Whether `v` occurs free in the [`TypeSchema`], i.e. somewhere not bound by a quantifier.
+    ///
+    /// This stops at the first occurrence rather than collecting every free variable as
+    /// [`free_vars`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(0, 1; @arrow[tp!(1), tp!(2), tp!(3)]); // ∀α. ∀β. β → ɣ → δ
+    /// assert!(t.contains_free_variable(2));
+    /// assert!(!t.contains_free_variable(1));
+    /// assert!(!t.contains_free_variable(4));
+    /// # }
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`free_vars`]: #method.free_vars
+    pub fn contains_free_variable(&self, v: Variable) -> bool {
+        match *self {
+            TypeSchema::Monotype(ref t) => t.contains_variable(v),
+            TypeSchema::Polytype { variable, ref body } => {
+                variable != v && body.contains_free_variable(v)
+            }
+        }
+    }
+    /// Removes each quantifier whose variable doesn't occur free in its body, such as one
+    /// shadowed by an inner quantifier of the same variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(0, 1, 2; @arrow[tp!(1), tp!(3)]); // ∀α. ∀β. ∀ɣ. β → δ
+    /// assert_eq!(t.prune_quantifiers(), ptp!(1; @arrow[tp!(1), tp!(3)]));
+    ///
+    /// let t = ptp!(0, 0; list(tp!(0))); // ∀α. ∀α. list(α)
+    /// assert_eq!(t.prune_quantifiers(), ptp!(0; list(tp!(0))));
+    /// # }
+    /// ```
+    pub fn prune_quantifiers(&self) -> TypeSchema<N> {
+        match *self {
+            TypeSchema::Monotype(ref t) => TypeSchema::Monotype(t.clone()),
+            TypeSchema::Polytype { variable, ref body } => {
+                let body = body.prune_quantifiers();
+                if body.contains_free_variable(variable) {
+                    TypeSchema::Polytype {
+                        variable,
+                        body: Box::new(body),
+                    }
+                } else {
+                    body
+                }
+            }
+        }
+    }
     /// Instantiate a [`TypeSchema`] in the context by removing quantifiers.
     ///
     /// All type variables will be replaced with fresh type variables.
@@ -165,6 +377,118 @@ impl<N: Name> TypeSchema<N> {
             }
         }
     }
+    /// Like [`instantiate`], but also returns the fresh variable which replaced each
+    /// quantified variable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.new_variable();
+    ///
+    /// let t = ptp!(3, 5; @arrow[tp!(3), tp!(5)]);
+    /// let (t, mapping) = t.instantiate_mapped(&mut ctx);
+    /// assert_eq!(t.to_string(), "t1 → t2");
+    /// assert_eq!(mapping[&3], 1);
+    /// assert_eq!(mapping[&5], 2);
+    /// # }
+    /// ```
+    ///
+    /// [`instantiate`]: #method.instantiate
+    pub fn instantiate_mapped(
+        &self,
+        ctx: &mut Context<N>,
+    ) -> (Type<N>, HashMap<Variable, Variable>) {
+        let mut substitution = HashMap::new();
+        let t = self.instantiate_internal(ctx, &mut substitution);
+        let mapping = substitution
+            .into_iter()
+            .filter_map(|(v, fresh)| match fresh {
+                Type::Variable(fresh) => Some((v, fresh)),
+                Type::Constructed(..) => None,
+            })
+            .collect();
+        (t, mapping)
+    }
+    /// Like [`instantiate`], but replaces each quantified variable with a fresh skolem (see
+    /// [`Context::new_skolem`]) rather than a unifiable variable.
+    ///
+    /// This is the rigid instantiation needed for subsumption checks between polymorphic types:
+    /// `σ ≤ τ` holds when `σ` instantiated normally unifies with `τ` skolemized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let id = ptp!(0; @arrow[tp!(0), tp!(0)]);
+    /// let not = ptp!(@arrow[tp!(bool), tp!(bool)]);
+    ///
+    /// // ∀α. α → α is more general than bool → bool...
+    /// let t = id.instantiate(&mut ctx);
+    /// let s = not.skolemize(&mut ctx);
+    /// assert!(ctx.clone().unify(&t, &s).is_ok());
+    ///
+    /// // ...but not the other way around
+    /// let t = not.instantiate(&mut ctx);
+    /// let s = id.skolemize(&mut ctx);
+    /// assert!(ctx.clone().unify(&t, &s).is_err());
+    /// # }
+    /// ```
+    ///
+    /// [`instantiate`]: #method.instantiate
+    /// [`Context::new_skolem`]: struct.Context.html#method.new_skolem
+    pub fn skolemize(&self, ctx: &mut Context<N>) -> Type<N> {
+        let substitution: HashMap<_, _> = self
+            .bound_vars()
+            .into_iter()
+            .map(|v| (v, ctx.new_skolem()))
+            .collect();
+        self.body().substitute(&substitution)
+    }
+    /// Like [`instantiate`], but the instantiated type is only constructed as far as it is
+    /// navigated, which saves work on large schemas of which only a part is inspected.
+    ///
+    /// The fresh variables are allocated immediately, so forcing the whole type gives the same
+    /// result as [`instantiate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// let t = ptp!(0, 1; @arrow[tp!(list(tp!(0))), tp!(1)]);
+    ///
+    /// let lazy = t.instantiate_lazy(&mut ctx);
+    /// assert_eq!(lazy.name(), Some(&"→"));
+    /// let args = lazy.args();
+    /// assert_eq!(args[1].variable(), Some(1));
+    /// assert_eq!(args[0].force(), tp!(list(tp!(0))));
+    ///
+    /// let mut ctx2 = Context::default();
+    /// assert_eq!(lazy.force(), t.instantiate(&mut ctx2));
+    /// # }
+    /// ```
+    ///
+    /// [`instantiate`]: #method.instantiate
+    pub fn instantiate_lazy<'a>(&'a self, ctx: &mut Context<N>) -> LazyType<'a, N> {
+        let substitution = self
+            .bound_vars()
+            .into_iter()
+            .map(|v| (v, ctx.new_variable()))
+            .collect();
+        LazyType {
+            tp: self.body(),
+            substitution: Rc::new(substitution),
+        }
+    }
     /// Parse a [`TypeSchema`] from a string. This round-trips with [`Display`].
     /// This is a **leaky** operation and should be avoided wherever possible:
     /// names of constructed types will remain until program termination.
@@ -190,9 +514,33 @@ impl<N: Name> TypeSchema<N> {
     ///
     /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
     /// [`TypeSchema`]: enum.TypeSchema.html
+    #[allow(clippy::result_unit_err)]
     pub fn parse(s: &str) -> Result<TypeSchema<N>, ()> {
         parse_typeschema(s)
     }
+    /// Like [`Display`], but variables are shown as lowercase letters. See
+    /// [`Type::show_letters`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = ptp!(0, 1; @arrow[tp!(@arrow[tp!(0), tp!(1)]), tp!(list(tp!(0))), tp!(list(tp!(1)))]);
+    /// assert_eq!(t.show_letters(), "∀a. ∀b. (a → b) → list(a) → list(b)");
+    /// # }
+    /// ```
+    ///
+    /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    /// [`Type::show_letters`]: enum.Type.html#method.show_letters
+    pub fn show_letters(&self) -> String {
+        match *self {
+            TypeSchema::Polytype { variable, ref body } => {
+                format!("∀{}. {}", lettered_variable(variable), body.show_letters())
+            }
+            TypeSchema::Monotype(ref t) => t.show_letters(),
+        }
+    }
 }
 impl<N: Name> fmt::Display for TypeSchema<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -300,6 +648,54 @@ pub enum Type<N: Name = &'static str> {
     Variable(Variable),
 }
 impl<N: Name> Type<N> {
+    /// Construct a type variable, like `tp!(0)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Type;
+    /// # fn main() {
+    /// let t: Type = Type::var(3);
+    /// assert_eq!(t, tp!(3));
+    /// # }
+    /// ```
+    pub fn var(v: Variable) -> Type<N> {
+        Type::Variable(v)
+    }
+    /// Construct a type from a constructor and its arguments. Unlike [`tp!`], the name needn't
+    /// be a literal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Type;
+    /// # fn main() {
+    /// let name = "list";
+    /// let t = Type::con(name, vec![Type::var(0)]);
+    /// assert_eq!(t, tp!(list(tp!(0))));
+    /// # }
+    /// ```
+    ///
+    /// [`tp!`]: macro.tp.html
+    pub fn con<I: IntoIterator<Item = Type<N>>>(name: N, args: I) -> Type<N> {
+        Type::Constructed(name, args.into_iter().collect())
+    }
+    /// Construct a type from a constructor without arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Type;
+    /// # fn main() {
+    /// assert_eq!(Type::nullary("int"), tp!(int));
+    /// # }
+    /// ```
+    pub fn nullary(name: N) -> Type<N> {
+        Type::Constructed(name, Vec::new())
+    }
     /// Construct a function type (i.e. `alpha` → `beta`).
     ///
     /// # Examples
@@ -335,49 +731,767 @@ impl<N: Name> Type<N> {
             _ => None,
         }
     }
-    pub(crate) fn occurs(&self, v: Variable) -> bool {
-        match *self {
-            Type::Constructed(_, ref args) => args.iter().any(|t| t.occurs(v)),
-            Type::Variable(n) => n == v,
-        }
+    /// Construct a fixed-length vector type: the constructor `name` applied to the element type
+    /// and the length. The length is an ordinary argument, such as a variable or a nullary
+    /// constructor standing for a type-level natural, so it unifies like any other subterm.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Type;
+    /// # fn main() {
+    /// let t = Type::vec("vec", tp!(int), Type::nullary("3"));
+    /// assert_eq!(t.to_string(), "vec(int,3)");
+    /// # }
+    /// ```
+    pub fn vec(name: N, elem: Type<N>, len: Type<N>) -> Type<N> {
+        Type::Constructed(name, vec![elem, len])
     }
-    /// Supplying `is_return` helps arrows look cleaner.
-    pub(crate) fn show(&self, is_return: bool) -> String {
+    /// If the type is a vector as constructed by [`Type::vec`] with the constructor `name`, get
+    /// its element type and length.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # use polytype::Type;
+    /// # fn main() {
+    /// let t = Type::vec("vec", tp!(int), tp!(0));
+    /// assert_eq!(t.as_vec(&"vec"), Some((&tp!(int), &tp!(0))));
+    /// assert_eq!(t.as_vec(&"array"), None);
+    /// assert_eq!(tp!(list(tp!(int))).as_vec(&"list"), None);
+    /// # }
+    /// ```
+    ///
+    /// [`Type::vec`]: #method.vec
+    pub fn as_vec(&self, name: &N) -> Option<(&Type<N>, &Type<N>)> {
         match *self {
-            Type::Variable(v) => format!("t{}", v),
-            Type::Constructed(ref name, ref args) => {
-                if args.is_empty() {
-                    name.show()
-                } else if name.is_arrow() {
-                    Type::arrow_show(args, is_return)
-                } else {
-                    format!(
-                        "{}({})",
-                        name.show(),
-                        args.iter().map(|t| t.show(true)).join(",")
-                    )
-                }
+            Type::Constructed(ref n, ref args) if n == name && args.len() == 2 => {
+                Some((&args[0], &args[1]))
             }
+            _ => None,
         }
     }
-    /// Show specifically for arrow types
-    fn arrow_show(args: &[Type<N>], is_return: bool) -> String {
-        if is_return {
-            format!("{} → {}", args[0].show(false), args[1].show(true))
-        } else {
-            format!("({} → {})", args[0].show(false), args[1].show(true))
-        }
-    }
-    /// If the type is an arrow, recursively get all curried function arguments.
+    /// A borrowed view of the type for navigating it without allocation.
     ///
     /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
     /// # fn main() {
-    /// let t = tp!(@arrow[tp!(int), tp!(int), tp!(bool)]);
-    /// if let Some(args) = t.args() {
-    ///     assert_eq!(args.len(), 2);
+    /// let t = tp!(@arrow[tp!(list(tp!(0))), tp!(int)]);
+    /// let cursor = t.cursor();
+    /// assert_eq!(cursor.name(), Some(&"→"));
+    ///
+    /// let arg = cursor.children().next().unwrap();
+    /// assert_eq!(arg.name(), Some(&"list"));
+    /// assert_eq!(arg.children().next().unwrap().variable(), Some(0));
+    /// # }
+    /// ```
+    pub fn cursor<'a>(&'a self) -> Cursor<'a, N> {
+        Cursor { tp: self }
+    }
+    /// The subterm at `path`, where each element indexes the arguments of a constructed type,
+    /// as in [`TypeDiff::path`]. This is `None` if the path leaves the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(int))), tp!(0)]);
+    /// assert_eq!(t.subterm(&[]), Some(&t));
+    /// assert_eq!(t.subterm(&[0, 0]), Some(&tp!(int)));
+    /// assert_eq!(t.subterm(&[0, 1]), None);
+    /// assert_eq!(t.subterm(&[1, 0]), None);
+    /// # }
+    /// ```
+    ///
+    /// [`TypeDiff::path`]: struct.TypeDiff.html#structfield.path
+    pub fn subterm(&self, path: &[usize]) -> Option<&Type<N>> {
+        path.iter().try_fold(self, |t, &i| match *t {
+            Type::Constructed(_, ref args) => args.get(i),
+            Type::Variable(_) => None,
+        })
+    }
+    /// A copy of the type with the subterm at `path` (see [`subterm`]) replaced by `new`. This
+    /// is `None` if the path leaves the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(int))), tp!(0)]);
+    /// assert_eq!(
+    ///     t.replace_at(&[0, 0], tp!(bool)),
+    ///     Some(tp!(@arrow[tp!(list(tp!(bool))), tp!(0)]))
+    /// );
+    /// assert_eq!(t.replace_at(&[2], tp!(bool)), None);
+    /// # }
+    /// ```
+    ///
+    /// [`subterm`]: #method.subterm
+    pub fn replace_at(&self, path: &[usize], new: Type<N>) -> Option<Type<N>> {
+        match path.split_first() {
+            None => Some(new),
+            Some((&i, rest)) => match *self {
+                Type::Constructed(ref name, ref args) => {
+                    let arg = args.get(i)?.replace_at(rest, new)?;
+                    let mut args = args.clone();
+                    args[i] = arg;
+                    Some(Type::Constructed(name.clone(), args))
+                }
+                Type::Variable(_) => None,
+            },
+        }
+    }
+    /// Whether the [`Variable`] appears anywhere in the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// assert!(t.contains_variable(0));
+    /// assert!(!t.contains_variable(1));
+    /// # }
+    /// ```
+    ///
+    /// [`Variable`]: type.Variable.html
+    pub fn contains_variable(&self, v: Variable) -> bool {
+        self.occurs(v)
+    }
+    pub(crate) fn occurs(&self, v: Variable) -> bool {
+        match *self {
+            Type::Constructed(_, ref args) => args.iter().any(|t| t.occurs(v)),
+            Type::Variable(n) => n == v,
+        }
+    }
+    /// Find where a [`Variable`] occurs in the type, as the path of argument indices leading to
+    /// its first occurrence. Useful for pointing at the offending position when unification
+    /// fails with [`UnificationError::Occurs`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// assert_eq!(t.occurs_path(0), Some(vec![1, 0]));
+    /// assert_eq!(t.occurs_path(1), None);
+    /// assert_eq!(tp!(0).occurs_path(0), Some(vec![]));
+    /// # }
+    /// ```
+    ///
+    /// [`Variable`]: type.Variable.html
+    /// [`UnificationError::Occurs`]: enum.UnificationError.html#variant.Occurs
+    pub fn occurs_path(&self, v: Variable) -> Option<Vec<usize>> {
+        match *self {
+            Type::Variable(n) if n == v => Some(vec![]),
+            Type::Variable(_) => None,
+            Type::Constructed(_, ref args) => args.iter().enumerate().find_map(|(i, t)| {
+                t.occurs_path(v).map(|mut path| {
+                    path.insert(0, i);
+                    path
+                })
+            }),
+        }
+    }
+    /// Aligns the structure of two types, reporting each position where they disagree: either
+    /// their constructors differ, or one is a variable and the other isn't. Positions beneath a
+    /// disagreement are not compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::TypeDiff;
+    /// let t1 = tp!(@arrow[tp!(int), tp!(list(tp!(0))), tp!(bool)]);
+    /// let t2 = tp!(@arrow[tp!(bool), tp!(list(tp!(1))), tp!(2)]);
+    /// assert_eq!(
+    ///     t1.diff(&t2),
+    ///     vec![
+    ///         TypeDiff { path: vec![0], left: tp!(int), right: tp!(bool) },
+    ///         TypeDiff { path: vec![1, 1], left: tp!(bool), right: tp!(2) },
+    ///     ]
+    /// );
+    /// assert!(t1.diff(&t1).is_empty());
+    /// # }
+    /// ```
+    pub fn diff(&self, other: &Type<N>) -> Vec<TypeDiff<N>> {
+        let mut diffs = Vec::new();
+        self.diff_internal(other, &mut Vec::new(), &mut diffs);
+        diffs
+    }
+    fn diff_internal(&self, other: &Type<N>, path: &mut Vec<usize>, diffs: &mut Vec<TypeDiff<N>>) {
+        match (self, other) {
+            (Type::Variable(_), Type::Variable(_)) => (),
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                if n1 == n2 && a1.len() == a2.len() =>
+            {
+                for (i, (t1, t2)) in a1.iter().zip(a2).enumerate() {
+                    path.push(i);
+                    t1.diff_internal(t2, path, diffs);
+                    path.pop();
+                }
+            }
+            _ => diffs.push(TypeDiff {
+                path: path.clone(),
+                left: self.clone(),
+                right: other.clone(),
+            }),
+        }
+    }
+    /// How closely the type resembles `query`, from `0.0` to `1.0`: the fraction of nodes in
+    /// `query` which match the type. A variable in `query` is a wildcard matching any subterm,
+    /// and a constructed type in `query` matches when the type has the same constructor and
+    /// arity there. Nothing below a mismatched node matches.
+    ///
+    /// Unlike unification, this ranks near misses, e.g. for searching by type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let query = tp!(@arrow[tp!(0), tp!(list(tp!(0)))]); // α → list(α)
+    /// assert_eq!(tp!(@arrow[tp!(int), tp!(list(tp!(bool)))]).structural_match_score(&query), 1.0);
+    /// assert_eq!(tp!(@arrow[tp!(int), tp!(int)]).structural_match_score(&query), 0.5);
+    /// assert_eq!(tp!(int).structural_match_score(&query), 0.0);
+    /// # }
+    /// ```
+    pub fn structural_match_score(&self, query: &Type<N>) -> f64 {
+        let (matched, total) = self.structural_match_internal(query);
+        matched as f64 / total as f64
+    }
+    /// The number of nodes in `query` which match, and the number of nodes in `query`.
+    fn structural_match_internal(&self, query: &Type<N>) -> (usize, usize) {
+        match (self, query) {
+            (_, &Type::Variable(_)) => (1, 1),
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                if n1 == n2 && a1.len() == a2.len() =>
+            {
+                a1.iter().zip(a2).fold((1, 1), |(matched, total), (t, q)| {
+                    let (m, n) = t.structural_match_internal(q);
+                    (matched + m, total + n)
+                })
+            }
+            _ => (0, query.total_node_count()),
+        }
+    }
+    /// Whether the type is an instance of `schema`: some choice of the schema's quantified
+    /// variables makes its body equal to the type. Variables in the type, and free variables in
+    /// the schema, are treated as constants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let schema = ptp!(0; @arrow[tp!(0), tp!(0)]); // ∀α. α → α
+    /// assert!(tp!(@arrow[tp!(int), tp!(int)]).is_instance_of(&schema));
+    /// assert!(tp!(@arrow[tp!(3), tp!(3)]).is_instance_of(&schema));
+    /// assert!(!tp!(@arrow[tp!(int), tp!(bool)]).is_instance_of(&schema));
+    ///
+    /// // the type's own variables can't be specialized
+    /// let schema = ptp!(@arrow[tp!(int), tp!(int)]);
+    /// assert!(!tp!(@arrow[tp!(0), tp!(0)]).is_instance_of(&schema));
+    /// # }
+    /// ```
+    pub fn is_instance_of(&self, schema: &TypeSchema<N>) -> bool {
+        Type::subsumes(schema, self).is_ok()
+    }
+    /// Find the substitution for the quantified variables of `general` which makes its body
+    /// equal to `specific`. Variables in `specific`, and free variables in `general`, are
+    /// rigid: they can't be specialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{SubsumptionError, Type};
+    /// let map = ptp!(0, 1; @arrow[
+    ///     tp!(@arrow[tp!(0), tp!(1)]),
+    ///     tp!(list(tp!(0))),
+    ///     tp!(list(tp!(1))),
+    /// ]);
+    /// let t = tp!(@arrow[
+    ///     tp!(@arrow[tp!(int), tp!(bool)]),
+    ///     tp!(list(tp!(int))),
+    ///     tp!(list(tp!(bool))),
+    /// ]);
+    /// let substitution = Type::subsumes(&map, &t).expect("map is more general");
+    /// assert_eq!(substitution[&0], tp!(int));
+    /// assert_eq!(substitution[&1], tp!(bool));
+    ///
+    /// let id = ptp!(0; @arrow[tp!(0), tp!(0)]);
+    /// assert_eq!(
+    ///     Type::subsumes(&id, &tp!(@arrow[tp!(int), tp!(bool)])),
+    ///     Err(SubsumptionError::Conflict(0, tp!(int), tp!(bool)))
+    /// );
+    /// assert_eq!(
+    ///     Type::subsumes(&ptp!(list(tp!(int))), &tp!(list(tp!(3)))),
+    ///     Err(SubsumptionError::Rigid(3, tp!(int)))
+    /// );
+    /// # }
+    /// ```
+    pub fn subsumes(
+        general: &TypeSchema<N>,
+        specific: &Type<N>,
+    ) -> Result<HashMap<Variable, Type<N>>, SubsumptionError<N>> {
+        let bound = general.bound_vars();
+        let mut substitution = HashMap::new();
+        general
+            .body()
+            .match_onto(specific, &bound, &mut substitution)?;
+        Ok(substitution)
+    }
+    /// One-way matching: substitute for the `bound` variables of `self` to make it equal to
+    /// `target`.
+    fn match_onto(
+        &self,
+        target: &Type<N>,
+        bound: &[Variable],
+        assignment: &mut HashMap<Variable, Type<N>>,
+    ) -> Result<(), SubsumptionError<N>> {
+        match (self, target) {
+            (Type::Variable(v), _) if bound.contains(v) => match assignment.get(v) {
+                Some(t) if t == target => Ok(()),
+                Some(t) => Err(SubsumptionError::Conflict(*v, t.clone(), target.clone())),
+                None => {
+                    assignment.insert(*v, target.clone());
+                    Ok(())
+                }
+            },
+            (Type::Variable(v), Type::Variable(w)) if v == w => Ok(()),
+            (Type::Variable(v), _) => Err(SubsumptionError::Rigid(*v, target.clone())),
+            (Type::Constructed(..), Type::Variable(w)) => {
+                Err(SubsumptionError::Rigid(*w, self.clone()))
+            }
+            (Type::Constructed(n1, _), Type::Constructed(n2, _)) if n1 != n2 => Err(
+                SubsumptionError::ConstructorMismatch(n1.clone(), n2.clone()),
+            ),
+            (Type::Constructed(n, a1), Type::Constructed(_, a2)) if a1.len() != a2.len() => Err(
+                SubsumptionError::ArityMismatch(n.clone(), a1.len(), a2.len()),
+            ),
+            (Type::Constructed(_, a1), Type::Constructed(_, a2)) => {
+                for (t1, t2) in a1.iter().zip(a2) {
+                    t1.match_onto(t2, bound, assignment)?;
+                }
+                Ok(())
+            }
+        }
+    }
+    /// Sorts the arguments of every constructor in `commutative` into a canonical order, so
+    /// types which differ only in the order of those arguments become equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use std::collections::HashSet;
+    /// let commutative: HashSet<_> = vec!["and"].into_iter().collect();
+    /// let t1 = tp!(list(tp!(and(tp!(int), tp!(0)))));
+    /// let t2 = tp!(list(tp!(and(tp!(0), tp!(int)))));
+    /// assert_ne!(t1, t2);
+    /// assert_eq!(
+    ///     t1.normalize_commutative(&commutative),
+    ///     t2.normalize_commutative(&commutative)
+    /// );
+    /// # }
+    /// ```
+    pub fn normalize_commutative(&self, commutative: &HashSet<N>) -> Type<N>
+    where
+        N: Ord + Hash,
+    {
+        match *self {
+            Type::Constructed(ref name, ref args) => {
+                let mut args: Vec<_> = args
+                    .iter()
+                    .map(|t| t.normalize_commutative(commutative))
+                    .collect();
+                if commutative.contains(name) {
+                    args.sort();
+                }
+                Type::Constructed(name.clone(), args)
+            }
+            Type::Variable(v) => Type::Variable(v),
+        }
+    }
+    /// Calls `f` on every node of the type, bottom-up: the arguments of a constructed type are
+    /// visited before the constructed type itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Type;
+    /// let mut t = tp!(@arrow[tp!(vec(tp!(int))), tp!(vec(tp!(0)))]);
+    /// t.walk_mut(|t| {
+    ///     if let Type::Constructed(ref mut name, _) = *t {
+    ///         if *name == "vec" {
+    ///             *name = "list";
+    ///         }
+    ///     }
+    /// });
+    /// assert_eq!(t, tp!(@arrow[tp!(list(tp!(int))), tp!(list(tp!(0)))]));
+    /// # }
+    /// ```
+    pub fn walk_mut<F: FnMut(&mut Type<N>)>(&mut self, mut f: F) {
+        self.walk_mut_internal(&mut f)
+    }
+    fn walk_mut_internal<F: FnMut(&mut Type<N>)>(&mut self, f: &mut F) {
+        if let Type::Constructed(_, ref mut args) = *self {
+            for arg in args {
+                arg.walk_mut_internal(f);
+            }
+        }
+        f(self)
+    }
+    /// Rewrite the type with `rule` until it no longer applies. Each pass visits every node
+    /// bottom-up, as with [`walk_mut`], replacing a node wherever `rule` gives `Some` new type.
+    /// Passes repeat until one changes nothing, or `max_passes` have been made for rules which
+    /// don't terminate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Type;
+    /// // add(zero, n) = n; add(succ(m), n) = succ(add(m, n))
+    /// let rule = |t: &Type| match *t {
+    ///     Type::Constructed("add", ref args) => match args[0] {
+    ///         Type::Constructed("zero", _) => Some(args[1].clone()),
+    ///         Type::Constructed("succ", ref m) => Some(tp!(succ(Type::Constructed(
+    ///             "add",
+    ///             vec![m[0].clone(), args[1].clone()],
+    ///         )))),
+    ///         _ => None,
+    ///     },
+    ///     _ => None,
+    /// };
+    /// let two = tp!(succ(tp!(succ(tp!(zero)))));
+    /// let t = tp!(list(tp!(add(two.clone(), tp!(zero)))));
+    /// assert_eq!(t.rewrite_fixpoint(100, rule), tp!(list(two)));
+    /// # }
+    /// ```
+    ///
+    /// [`walk_mut`]: #method.walk_mut
+    pub fn rewrite_fixpoint<F>(&self, max_passes: usize, mut rule: F) -> Type<N>
+    where
+        F: FnMut(&Type<N>) -> Option<Type<N>>,
+    {
+        let mut t = self.clone();
+        for _ in 0..max_passes {
+            let mut changed = false;
+            t.walk_mut(|node| {
+                if let Some(rewritten) = rule(node) {
+                    *node = rewritten;
+                    changed = true;
+                }
+            });
+            if !changed {
+                break;
+            }
+        }
+        t
+    }
+    /// Supplying `is_return` helps arrows look cleaner.
+    pub(crate) fn show(&self, is_return: bool) -> String {
+        self.show_with(is_return, numbered_variable)
+    }
+    /// Like [`Display`], but variables are shown as lowercase letters rather than numbered
+    /// (`a`, …, `z`, then `a1`, …, `z1`, and so on).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(@arrow[tp!(0), tp!(1)]), tp!(list(tp!(0))), tp!(list(tp!(27)))]);
+    /// assert_eq!(t.show_letters(), "(a → b) → list(a) → list(b1)");
+    /// # }
+    /// ```
+    ///
+    /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    pub fn show_letters(&self) -> String {
+        self.show_with(true, lettered_variable)
+    }
+    /// Like [`Display`], but breaks the type over several indented lines wherever it doesn't
+    /// fit within `width` characters: each argument of an arrow goes on its own line, as does
+    /// each argument of a constructed type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[
+    ///     tp!(@arrow[tp!(0), tp!(bool)]),
+    ///     tp!(list(tp!(0))),
+    ///     tp!(pair(tp!(list(tp!(0))), tp!(list(tp!(0))))),
+    /// ]);
+    /// assert_eq!(t.pretty(80), t.to_string());
+    /// assert_eq!(
+    ///     t.pretty(20),
+    ///     "(t0 → bool) →
+    /// list(t0) →
+    /// pair(
+    ///     list(t0),
+    ///     list(t0)
+    /// )"
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    pub fn pretty(&self, width: usize) -> String {
+        self.pretty_lines(width, true).join("\n")
+    }
+    fn pretty_lines(&self, width: usize, is_return: bool) -> Vec<String> {
+        let flat = self.show(is_return);
+        if flat.chars().count() <= width {
+            return vec![flat];
+        }
+        let inner = width.saturating_sub(4);
+        match *self {
+            Type::Variable(_) => vec![flat],
+            Type::Constructed(_, ref args) if args.is_empty() => vec![flat],
+            Type::Constructed(ref name, _) if name.is_arrow() => {
+                let width = if is_return { width } else { inner };
+                let (mut lines, ret) = self.fold_args(Vec::new(), |mut lines, arg| {
+                    let mut arg_lines = arg.pretty_lines(width, false);
+                    if let Some(last) = arg_lines.last_mut() {
+                        last.push_str(" →");
+                    }
+                    lines.extend(arg_lines);
+                    lines
+                });
+                lines.extend(ret.pretty_lines(width, true));
+                if is_return {
+                    lines
+                } else {
+                    wrap_lines("(", lines, ")")
+                }
+            }
+            Type::Constructed(ref name, ref args) => {
+                let mut lines = Vec::new();
+                for (i, arg) in args.iter().enumerate() {
+                    let mut arg_lines = arg.pretty_lines(inner, true);
+                    if i + 1 < args.len() {
+                        if let Some(last) = arg_lines.last_mut() {
+                            last.push(',');
+                        }
+                    }
+                    lines.extend(arg_lines);
+                }
+                wrap_lines(&format!("{}(", name.show()), lines, ")")
+            }
+        }
+    }
+    /// Show the type as a Prolog term: constructors become functors (arrows become `arrow/2`),
+    /// and variable `tN` becomes the Prolog variable `TN`. Names which aren't plain Prolog
+    /// atoms are quoted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(0))), tp!(Int), tp!(1)]);
+    /// assert_eq!(t.to_prolog(), "arrow(list(T0), arrow('Int', T1))");
+    /// # }
+    /// ```
+    pub fn to_prolog(&self) -> String {
+        match *self {
+            Type::Variable(v) => format!("T{}", v),
+            Type::Constructed(ref name, ref args) => {
+                let functor = if name.is_arrow() {
+                    String::from("arrow")
+                } else {
+                    prolog_atom(&name.show())
+                };
+                if args.is_empty() {
+                    functor
+                } else {
+                    format!(
+                        "{}({})",
+                        functor,
+                        args.iter().map(Type::to_prolog).join(", ")
+                    )
+                }
+            }
+        }
+    }
+    /// Show the type as LaTeX math: arrows become `\to`, variable `tN` becomes `\tau_{N}`, and
+    /// constructors are set in `\mathsf{}` and applied to their arguments with thin spaces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(@arrow[tp!(0), tp!(bool)]), tp!(list(tp!(0))), tp!(list(tp!(0)))]);
+    /// assert_eq!(
+    ///     t.to_latex(),
+    ///     r"(\tau_{0} \to \mathsf{bool}) \to \mathsf{list}\,\tau_{0} \to \mathsf{list}\,\tau_{0}"
+    /// );
+    /// let t = tp!(pair(tp!(list(tp!(1))), tp!(@arrow[tp!(0), tp!(1)])));
+    /// assert_eq!(
+    ///     t.to_latex(),
+    ///     r"\mathsf{pair}\,(\mathsf{list}\,\tau_{1})\,(\tau_{0} \to \tau_{1})"
+    /// );
+    /// # }
+    /// ```
+    pub fn to_latex(&self) -> String {
+        self.latex(LatexPosition::Return)
+    }
+    fn latex(&self, position: LatexPosition) -> String {
+        match *self {
+            Type::Variable(v) => format!("\\tau_{{{}}}", v),
+            Type::Constructed(ref name, ref args) if args.is_empty() => {
+                format!("\\mathsf{{{}}}", latex_escape(&name.show()))
+            }
+            Type::Constructed(ref name, ref args) if name.is_arrow() => {
+                let shown = format!(
+                    "{} \\to {}",
+                    args[0].latex(LatexPosition::Argument),
+                    args[1].latex(LatexPosition::Return)
+                );
+                if position == LatexPosition::Return {
+                    shown
+                } else {
+                    format!("({})", shown)
+                }
+            }
+            Type::Constructed(ref name, ref args) => {
+                let mut shown = format!("\\mathsf{{{}}}", latex_escape(&name.show()));
+                for arg in args {
+                    shown.push_str("\\,");
+                    shown.push_str(&arg.latex(LatexPosition::Applied));
+                }
+                if position == LatexPosition::Applied {
+                    format!("({})", shown)
+                } else {
+                    shown
+                }
+            }
+        }
+    }
+    /// Show the type, using `show_var` to render each variable.
+    fn show_with(&self, is_return: bool, show_var: fn(Variable) -> String) -> String {
+        match *self {
+            Type::Variable(v) => show_var(v),
+            Type::Constructed(ref name, ref args) => {
+                if args.is_empty() {
+                    name.show()
+                } else if name.is_arrow() {
+                    Type::arrow_show(args, is_return, show_var)
+                } else {
+                    format!(
+                        "{}({})",
+                        name.show(),
+                        args.iter().map(|t| t.show_with(true, show_var)).join(",")
+                    )
+                }
+            }
+        }
+    }
+    /// Show specifically for arrow types
+    fn arrow_show(args: &[Type<N>], is_return: bool, show_var: fn(Variable) -> String) -> String {
+        let alpha = args[0].show_with(false, show_var);
+        let beta = args[1].show_with(true, show_var);
+        if is_return {
+            format!("{} → {}", alpha, beta)
+        } else {
+            format!("({} → {})", alpha, beta)
+        }
+    }
+    /// Whether the type is an arrow, as constructed by [`Type::arrow`] or `tp!(@arrow[...])`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// assert!(tp!(@arrow[tp!(int), tp!(bool)]).is_arrow());
+    /// assert!(!tp!(list(tp!(int))).is_arrow());
+    /// assert!(!tp!(0).is_arrow());
+    /// # }
+    /// ```
+    ///
+    /// [`Type::arrow`]: #method.arrow
+    pub fn is_arrow(&self) -> bool {
+        self.split_arrow().is_some()
+    }
+    /// If the type is an arrow, get its domain and codomain. Unlike [`args`], this only looks
+    /// at the outermost arrow, so the codomain of a curried function is itself an arrow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(int), tp!(bool)]);
+    /// let (domain, codomain) = t.split_arrow().expect("an arrow");
+    /// assert_eq!(domain, &tp!(int));
+    /// assert_eq!(codomain, &tp!(@arrow[tp!(int), tp!(bool)]));
+    ///
+    /// assert_eq!(tp!(int).split_arrow(), None);
+    /// # }
+    /// ```
+    ///
+    /// [`args`]: #method.args
+    pub fn split_arrow(&self) -> Option<(&Type<N>, &Type<N>)> {
+        match *self {
+            Type::Constructed(ref n, ref args) if n.is_arrow() && args.len() == 2 => {
+                Some((&args[0], &args[1]))
+            }
+            _ => None,
+        }
+    }
+    /// Whether the type is first-order: no arrow anywhere within it has an arrow as its domain,
+    /// i.e. no function takes a function as an argument.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// assert!(tp!(@arrow[tp!(int), tp!(list(tp!(0))), tp!(bool)]).is_first_order());
+    /// assert!(!tp!(@arrow[tp!(@arrow[tp!(0), tp!(1)]), tp!(list(tp!(0)))]).is_first_order());
+    /// assert!(!tp!(list(tp!(@arrow[tp!(@arrow[tp!(0), tp!(0)]), tp!(0)]))).is_first_order());
+    /// # }
+    /// ```
+    pub fn is_first_order(&self) -> bool {
+        match self.split_arrow() {
+            Some((domain, codomain)) => {
+                !domain.is_arrow() && domain.is_first_order() && codomain.is_first_order()
+            }
+            None => match *self {
+                Type::Constructed(_, ref args) => args.iter().all(Type::is_first_order),
+                Type::Variable(_) => true,
+            },
+        }
+    }
+    /// If the type is an arrow, recursively get all curried function arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(int), tp!(bool)]);
+    /// if let Some(args) = t.args() {
+    ///     assert_eq!(args.len(), 2);
     ///     assert_eq!(args[0].to_string(), "int");
     ///     assert_eq!(args[1].to_string(), "int");
     /// } else { unreachable!() }
@@ -433,6 +1547,110 @@ impl<N: Name> Type<N> {
             _ => None,
         }
     }
+    /// Fold `f` over each curried function argument, from first to last, returning the
+    /// accumulator along with the ultimate return type.
+    ///
+    /// A type which isn't an arrow has no arguments and is its own return type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(bool))), tp!(bool)]);
+    /// let (names, ret) = t.fold_args(Vec::new(), |mut names, arg| {
+    ///     names.push(arg.to_string());
+    ///     names
+    /// });
+    /// assert_eq!(names, vec!["int", "list(bool)"]);
+    /// assert_eq!(ret.to_string(), "bool");
+    ///
+    /// let t = tp!(int);
+    /// let (count, ret) = t.fold_args(0, |n, _| n + 1);
+    /// assert_eq!(count, 0);
+    /// assert_eq!(ret.to_string(), "int");
+    /// # }
+    /// ```
+    pub fn fold_args<A, F: FnMut(A, &Type<N>) -> A>(&self, init: A, mut f: F) -> (A, &Type<N>) {
+        let mut acc = init;
+        let mut tp = self;
+        loop {
+            match *tp {
+                Type::Constructed(ref n, ref args) if n.is_arrow() => {
+                    acc = f(acc, &args[0]);
+                    tp = &args[1];
+                }
+                _ => return (acc, tp),
+            }
+        }
+    }
+    /// Consumes the type, returning each curried function argument, from first to last, and the
+    /// ultimate return type. Unlike [`args`] and [`returns`], the pieces are owned rather than
+    /// borrowed. A type which isn't an arrow has no arguments and is its own return type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(bool))), tp!(bool)]);
+    /// let (args, ret) = t.into_arrow_spine();
+    /// assert_eq!(args, vec![tp!(int), tp!(list(tp!(bool)))]);
+    /// assert_eq!(ret, tp!(bool));
+    ///
+    /// assert_eq!(tp!(int).into_arrow_spine(), (vec![], tp!(int)));
+    /// # }
+    /// ```
+    ///
+    /// [`args`]: #method.args
+    /// [`returns`]: #method.returns
+    pub fn into_arrow_spine(self) -> (Vec<Type<N>>, Type<N>) {
+        let mut args = Vec::new();
+        let mut tp = self;
+        loop {
+            match tp {
+                Type::Constructed(ref n, ref mut a) if n.is_arrow() && a.len() == 2 => {
+                    let ret = a.pop().unwrap();
+                    args.push(a.pop().unwrap());
+                    tp = ret;
+                }
+                tp => return (args, tp),
+            }
+        }
+    }
+    /// Flattens nested applications of the binary constructor `apply_name`, so `((f a) b) c`
+    /// becomes the head `f` and the arguments `[a, b, c]`. A type which isn't such an
+    /// application is its own head, with no arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(ap(tp!(ap(tp!(either), tp!(int))), tp!(bool)));
+    /// let (head, args) = t.uncurry_application(&"ap");
+    /// assert_eq!(head, &tp!(either));
+    /// assert_eq!(args, vec![&tp!(int), &tp!(bool)]);
+    ///
+    /// let t = tp!(list(tp!(int)));
+    /// assert_eq!(t.uncurry_application(&"ap"), (&t, vec![]));
+    /// # }
+    /// ```
+    pub fn uncurry_application(&self, apply_name: &N) -> (&Type<N>, Vec<&Type<N>>) {
+        let mut head = self;
+        let mut args = Vec::new();
+        loop {
+            match *head {
+                Type::Constructed(ref n, ref a) if n == apply_name && a.len() == 2 => {
+                    args.push(&a[1]);
+                    head = &a[0];
+                }
+                _ => break,
+            }
+        }
+        args.reverse();
+        (head, args)
+    }
     /// Applies the type in a [`Context`].
     ///
     /// This will substitute type variables for the values associated with them
@@ -485,39 +1703,143 @@ impl<N: Name> Type<N> {
             }
         }
     }
-    /// Generalizes the type by quantifying over free variables in a [`TypeSchema`].
+    /// Fully resolves the type in a [`Context`], so that no variable in the result is bound by
+    /// the context. Unlike [`apply`], which substitutes each variable once, this also resolves
+    /// the variables of each substituted type, following chains of bindings to their end.
+    ///
+    /// A variable whose binding refers back to itself, which only [`Context::extend`] can
+    /// create, is left in place where it recurs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(list(tp!(1))));
+    /// ctx.extend(1, tp!(2));
+    /// ctx.extend(2, tp!(int));
+    ///
+    /// let t = tp!(@arrow[tp!(0), tp!(3)]);
+    /// assert_eq!(t.apply(&ctx), tp!(@arrow[tp!(list(tp!(1))), tp!(3)]));
+    /// assert_eq!(t.zonk(&ctx), tp!(@arrow[tp!(list(tp!(int))), tp!(3)]));
+    /// # }
+    /// ```
+    ///
+    /// [`Context`]: struct.Context.html
+    /// [`Context::extend`]: struct.Context.html#method.extend
+    /// [`apply`]: #method.apply
+    pub fn zonk(&self, ctx: &Context<N>) -> Type<N> {
+        self.zonk_internal(ctx, &mut Vec::new())
+    }
+    fn zonk_internal(&self, ctx: &Context<N>, resolving: &mut Vec<Variable>) -> Type<N> {
+        match *self {
+            Type::Constructed(ref name, ref args) => {
+                let mut zonked = Vec::with_capacity(args.len());
+                for t in args {
+                    zonked.push(t.zonk_internal(ctx, resolving));
+                }
+                Type::Constructed(name.clone(), zonked)
+            }
+            Type::Variable(v) => match ctx.substitution.get(&v) {
+                Some(t) if !resolving.contains(&v) => {
+                    resolving.push(v);
+                    let t = t.zonk_internal(ctx, resolving);
+                    resolving.pop();
+                    t
+                }
+                _ => Type::Variable(v),
+            },
+        }
+    }
+    /// Generalizes the type by quantifying over free variables in a [`TypeSchema`].
+    ///
+    /// Variables specified by `bound` remain unquantified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{Context, Type};
+    /// let t = tp!(@arrow[tp!(0), tp!(1)]);
+    /// assert_eq!(t.to_string(), "t0 → t1");
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(int));
+    ///
+    /// let t_gen = t.apply(&ctx).generalize(&[]);
+    /// assert_eq!(t_gen.to_string(), "∀t1. int → t1");
+    ///
+    /// let t_gen = t.apply(&ctx).generalize(&[1]);
+    /// assert_eq!(t_gen.to_string(), "int → t1");
+    /// # }
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    pub fn generalize(&self, bound: &[Variable]) -> TypeSchema<N> {
+        let fvs = self
+            .vars()
+            .into_iter()
+            .filter(|x| !bound.contains(x))
+            .collect::<Vec<Variable>>();
+        let mut t = TypeSchema::Monotype(self.clone());
+        for v in fvs {
+            t = TypeSchema::Polytype {
+                variable: v,
+                body: Box::new(t),
+            };
+        }
+        t
+    }
+    /// Wrap the type, without cloning it, as a [`TypeSchema::Monotype`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(0), tp!(int)]);
+    /// assert_eq!(t.into_schema(), ptp!(@arrow[tp!(0), tp!(int)]));
+    /// # }
+    /// ```
+    ///
+    /// [`TypeSchema::Monotype`]: enum.TypeSchema.html#variant.Monotype
+    pub fn into_schema(self) -> TypeSchema<N> {
+        TypeSchema::Monotype(self)
+    }
+    /// Fully resolves the type in a [`Context`] (see [`zonk`]) and quantifies over every
+    /// remaining variable, with quantifiers in ascending order of variable.
     ///
-    /// Variables specified by `bound` remain unquantified.
+    /// This is [`generalize`] for when nothing in the environment is free, such as for
+    /// top-level definitions.
     ///
     /// # Examples
     ///
     /// ```
     /// # #[macro_use] extern crate polytype;
     /// # fn main() {
-    /// # use polytype::{Context, Type};
-    /// let t = tp!(@arrow[tp!(0), tp!(1)]);
-    /// assert_eq!(t.to_string(), "t0 → t1");
+    /// # use polytype::Context;
+    /// let t = tp!(@arrow[tp!(2), tp!(0), tp!(1)]);
     ///
     /// let mut ctx = Context::default();
-    /// ctx.extend(0, tp!(int));
+    /// ctx.extend(1, tp!(list(tp!(3))));
     ///
-    /// let t_gen = t.apply(&ctx).generalize(&[]);
-    /// assert_eq!(t_gen.to_string(), "∀t1. int → t1");
-    ///
-    /// let t_gen = t.apply(&ctx).generalize(&[1]);
-    /// assert_eq!(t_gen.to_string(), "int → t1");
+    /// let t_gen = t.generalize_all(&ctx);
+    /// assert_eq!(t_gen.to_string(), "∀t0. ∀t2. ∀t3. t2 → t0 → list(t3)");
     /// # }
     /// ```
     ///
-    /// [`TypeSchema`]: enum.TypeSchema.html
-    pub fn generalize(&self, bound: &[Variable]) -> TypeSchema<N> {
-        let fvs = self
-            .vars()
-            .into_iter()
-            .filter(|x| !bound.contains(x))
-            .collect::<Vec<Variable>>();
-        let mut t = TypeSchema::Monotype(self.clone());
-        for v in fvs {
+    /// [`Context`]: struct.Context.html
+    /// [`generalize`]: #method.generalize
+    /// [`zonk`]: #method.zonk
+    pub fn generalize_all(&self, ctx: &Context<N>) -> TypeSchema<N> {
+        let t = self.zonk(ctx);
+        let mut fvs = t.vars();
+        fvs.sort_unstable();
+        let mut t = TypeSchema::Monotype(t);
+        for v in fvs.into_iter().rev() {
             t = TypeSchema::Polytype {
                 variable: v,
                 body: Box::new(t),
@@ -525,6 +1847,35 @@ impl<N: Name> Type<N> {
         }
         t
     }
+    /// Fully resolves the type in a [`Context`] (see [`zonk`]) and quantifies over exactly the
+    /// variables in `order`, outermost first, whether or not they occur in the type. Variables
+    /// not in `order` remain free.
+    ///
+    /// Unlike [`generalize_all`], this keeps the positions of quantifiers under the caller's
+    /// control.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Context;
+    /// let t = tp!(@arrow[tp!(2), tp!(0), tp!(1)]);
+    ///
+    /// let mut ctx = Context::default();
+    /// ctx.extend(1, tp!(list(tp!(3))));
+    ///
+    /// let t_gen = t.generalize_ordered(&[3, 2, 0], &ctx);
+    /// assert_eq!(t_gen.to_string(), "∀t3. ∀t2. ∀t0. t2 → t0 → list(t3)");
+    /// # }
+    /// ```
+    ///
+    /// [`Context`]: struct.Context.html
+    /// [`generalize_all`]: #method.generalize_all
+    /// [`zonk`]: #method.zonk
+    pub fn generalize_ordered(&self, order: &[Variable], ctx: &Context<N>) -> TypeSchema<N> {
+        TypeSchema::quantify(order, self.zonk(ctx))
+    }
     /// Compute all the variables present in a type.
     ///
     /// # Examples
@@ -556,6 +1907,149 @@ impl<N: Name> Type<N> {
             }
         }
     }
+    /// Like [`vars`], but pushes onto `out` rather than allocating, so one buffer can be reused
+    /// across many types. Each variable of the type is pushed in order of first occurrence,
+    /// unless `out` already contains it. `out` is not cleared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let mut out = vec![7];
+    /// tp!(@arrow[tp!(1), tp!(list(tp!(0))), tp!(1)]).free_variables_into(&mut out);
+    /// assert_eq!(out, vec![7, 1, 0]);
+    ///
+    /// out.clear();
+    /// tp!(list(tp!(2))).free_variables_into(&mut out);
+    /// assert_eq!(out, vec![2]);
+    /// # }
+    /// ```
+    ///
+    /// [`vars`]: #method.vars
+    pub fn free_variables_into(&self, out: &mut Vec<Variable>) {
+        match *self {
+            Type::Constructed(_, ref args) => {
+                for arg in args {
+                    arg.free_variables_into(out);
+                }
+            }
+            Type::Variable(v) => {
+                if !out.contains(&v) {
+                    out.push(v);
+                }
+            }
+        }
+    }
+    /// Pushes each constructor name of the type onto `out` in order of first occurrence, unless
+    /// `out` already contains it. `out` is not cleared, so one buffer can be reused across many
+    /// types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(int))), tp!(int), tp!(0)]);
+    /// let mut out = Vec::new();
+    /// t.constructors_into(&mut out);
+    /// assert_eq!(out, vec![&"→", &"list", &"int"]);
+    /// # }
+    /// ```
+    pub fn constructors_into<'a>(&'a self, out: &mut Vec<&'a N>) {
+        if let Type::Constructed(ref name, ref args) = *self {
+            if !out.contains(&name) {
+                out.push(name);
+            }
+            for arg in args {
+                arg.constructors_into(out);
+            }
+        }
+    }
+    /// Count the occurrences of each constructor name in a type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(int))), tp!(int), tp!(0)]);
+    /// let histogram = t.constructor_histogram();
+    /// assert_eq!(histogram["→"], 2);
+    /// assert_eq!(histogram["int"], 2);
+    /// assert_eq!(histogram["list"], 1);
+    /// assert_eq!(histogram.len(), 3);
+    /// # }
+    /// ```
+    pub fn constructor_histogram(&self) -> HashMap<N, usize>
+    where
+        N: Hash,
+    {
+        let mut histogram = HashMap::new();
+        self.constructor_histogram_internal(&mut histogram);
+        histogram
+    }
+    fn constructor_histogram_internal(&self, histogram: &mut HashMap<N, usize>)
+    where
+        N: Hash,
+    {
+        if let Type::Constructed(ref name, ref args) = *self {
+            *histogram.entry(name.clone()).or_insert(0) += 1;
+            for arg in args {
+                arg.constructor_histogram_internal(histogram);
+            }
+        }
+    }
+    /// The number of nodes in the type: each variable and constructed type within it,
+    /// including itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(int))), tp!(list(tp!(int)))]);
+    /// assert_eq!(t.total_node_count(), 5);
+    /// assert_eq!(tp!(0).total_node_count(), 1);
+    /// # }
+    /// ```
+    pub fn total_node_count(&self) -> usize {
+        match *self {
+            Type::Constructed(_, ref args) => {
+                1 + args.iter().map(Type::total_node_count).sum::<usize>()
+            }
+            Type::Variable(_) => 1,
+        }
+    }
+    /// The number of distinct nodes in the type, where structurally equal subterms count once.
+    /// Compared with [`total_node_count`], this measures how much of the type is duplicated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(int))), tp!(list(tp!(int)))]);
+    /// assert_eq!(t.unique_node_count(), 3);
+    /// # }
+    /// ```
+    ///
+    /// [`total_node_count`]: #method.total_node_count
+    pub fn unique_node_count(&self) -> usize
+    where
+        N: Hash,
+    {
+        let mut seen = HashSet::new();
+        let mut pending = vec![self];
+        while let Some(t) = pending.pop() {
+            if seen.insert(t) {
+                if let Type::Constructed(_, ref args) = *t {
+                    pending.extend(args);
+                }
+            }
+        }
+        seen.len()
+    }
     /// Perform a substitution. This is analogous to [`apply`].
     ///
     /// # Examples
@@ -605,6 +2099,58 @@ impl<N: Name> Type<N> {
             }
         }
     }
+    /// Replace each variable `v` in the type with `f(v)`. The replacements themselves are left
+    /// as they are, so `f` isn't called on variables within them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::Type;
+    /// let t = tp!(@arrow[tp!(0), tp!(list(tp!(1)))]);
+    /// let t = t.map_variables(|v| {
+    ///     if v == 0 {
+    ///         tp!(int)
+    ///     } else {
+    ///         tp!(list(Type::Variable(v + 1)))
+    ///     }
+    /// });
+    /// assert_eq!(t, tp!(@arrow[tp!(int), tp!(list(tp!(list(tp!(2)))))]));
+    /// # }
+    /// ```
+    pub fn map_variables<F: FnMut(Variable) -> Type<N>>(&self, mut f: F) -> Type<N> {
+        self.map_variables_internal(&mut f)
+    }
+    fn map_variables_internal<F: FnMut(Variable) -> Type<N>>(&self, f: &mut F) -> Type<N> {
+        match *self {
+            Type::Constructed(ref name, ref args) => {
+                let args = args.iter().map(|t| t.map_variables_internal(f)).collect();
+                Type::Constructed(name.clone(), args)
+            }
+            Type::Variable(v) => f(v),
+        }
+    }
+    /// Replace every variable in the type with `default`, e.g. to ground the variables which
+    /// remain after inference. The type should already be applied (see [`apply`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(list(tp!(0))), tp!(1)]);
+    /// assert_eq!(
+    ///     t.default_free_variables(&tp!(unit)),
+    ///     tp!(@arrow[tp!(list(tp!(unit))), tp!(unit)])
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// [`apply`]: #method.apply
+    pub fn default_free_variables(&self, default: &Type<N>) -> Type<N> {
+        self.map_variables(|_| default.clone())
+    }
     /// Parse a type from a string. This round-trips with [`Display`]. This is a
     /// **leaky** operation and should be avoided wherever possible: names of
     /// constructed types will remain until program termination.
@@ -633,9 +2179,352 @@ impl<N: Name> Type<N> {
     /// ```
     ///
     /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    #[allow(clippy::result_unit_err)]
     pub fn parse(s: &str) -> Result<Type<N>, ()> {
         parse_type(s)
     }
+    /// Like [`parse`], but each constructor identifier is turned into a name using `resolve`
+    /// rather than [`Name::parse`]. This is not leaky unless `resolve` is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{Name, Type};
+    /// #[derive(Debug, Clone, PartialEq, Eq)]
+    /// struct Symbol(usize);
+    /// impl Name for Symbol {
+    ///     fn arrow() -> Self {
+    ///         Symbol(0)
+    ///     }
+    /// }
+    ///
+    /// let mut symbols = vec!["→"];
+    /// let t = Type::parse_with("int -> list(int)", |s| {
+    ///     let id = symbols.iter().position(|&x| x == s).unwrap_or_else(|| {
+    ///         symbols.push(Box::leak(s.to_string().into_boxed_str()));
+    ///         symbols.len() - 1
+    ///     });
+    ///     Symbol(id)
+    /// }).expect("valid type");
+    ///
+    /// let tint = Type::Constructed(Symbol(1), vec![]);
+    /// assert_eq!(
+    ///     t,
+    ///     Type::arrow(tint.clone(), Type::Constructed(Symbol(2), vec![tint]))
+    /// );
+    /// ```
+    ///
+    /// [`parse`]: #method.parse
+    /// [`Name::parse`]: trait.Name.html#method.parse
+    pub fn parse_with<F: FnMut(&str) -> N>(s: &str, resolve: F) -> Result<Type<N>, ParseError> {
+        parse_type_with(s, resolve)
+    }
+    /// Encode the type in a compact, unambiguous format that round-trips exactly with
+    /// [`decode`], including variable numbering. Unlike [`Display`], this is meant for machines
+    /// (e.g. on-disk caches) rather than people.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(3)))]);
+    /// assert_eq!(t.encode(), "c2:3:→c0:3:intc1:4:listv3");
+    /// # }
+    /// ```
+    ///
+    /// [`decode`]: #method.decode
+    /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    pub fn encode(&self) -> String {
+        let mut s = String::new();
+        encode_type(self, &mut s);
+        s
+    }
+    /// Decode a type from the format produced by [`encode`]. Names are read with
+    /// [`Name::parse`], so this is **leaky** for `&'static str` just like [`parse`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate polytype;
+    /// # fn main() {
+    /// # use polytype::{DecodeError, Type};
+    /// let t = tp!(hashmap(tp!(str), tp!(@arrow[tp!(7), tp!(bool)])));
+    /// let decoded: Type = Type::decode(&t.encode()).expect("valid encoding");
+    /// assert_eq!(decoded, t);
+    ///
+    /// let res: Result<Type, _> = Type::decode("c1:4:list");
+    /// assert_eq!(res, Err(DecodeError::UnexpectedEnd));
+    /// # }
+    /// ```
+    ///
+    /// [`encode`]: #method.encode
+    /// [`parse`]: #method.parse
+    /// [`Name::parse`]: trait.Name.html#method.parse
+    pub fn decode(s: &str) -> Result<Type<N>, DecodeError> {
+        decode_type(s)
+    }
+}
+/// A position where two types disagree, as found by [`Type::diff`].
+///
+/// [`Type::diff`]: enum.Type.html#method.diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDiff<N: Name = &'static str> {
+    /// The path of argument indices leading to the position from the root of both types.
+    pub path: Vec<usize>,
+    /// The subtype of the left type at the position.
+    pub left: Type<N>,
+    /// The subtype of the right type at the position.
+    pub right: Type<N>,
+}
+
+/// Errors from [`Type::subsumes`].
+///
+/// [`Type::subsumes`]: enum.Type.html#method.subsumes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubsumptionError<N: Name = &'static str> {
+    /// The general and specific types have different constructors at the same position.
+    ConstructorMismatch(N, N),
+    /// The constructor is applied to a different number of arguments in the general type than
+    /// in the specific type.
+    ArityMismatch(N, usize, usize),
+    /// A variable which can't be specialized would have to equal the type.
+    Rigid(Variable, Type<N>),
+    /// A quantified variable would have to equal two different types.
+    Conflict(Variable, Type<N>, Type<N>),
+}
+impl<N: Name> fmt::Display for SubsumptionError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            SubsumptionError::ConstructorMismatch(ref n1, ref n2) => {
+                write!(f, "ConstructorMismatch({}, {})", n1.show(), n2.show())
+            }
+            SubsumptionError::ArityMismatch(ref n, a1, a2) => {
+                write!(f, "ArityMismatch({}, {}, {})", n.show(), a1, a2)
+            }
+            SubsumptionError::Rigid(v, ref t) => write!(f, "Rigid(t{}, {})", v, t),
+            SubsumptionError::Conflict(v, ref t1, ref t2) => {
+                write!(f, "Conflict(t{}, {}, {})", v, t1, t2)
+            }
+        }
+    }
+}
+impl<N: Name + fmt::Debug> error::Error for SubsumptionError<N> {
+    fn description(&self) -> &'static str {
+        "subsumption failed"
+    }
+}
+
+/// An error from [`infer_arities`]: the constructor was used with two different numbers of
+/// arguments.
+///
+/// [`infer_arities`]: fn.infer_arities.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArityConflict<N: Name = &'static str> {
+    /// The constructor's name.
+    pub name: N,
+    /// The arity of the constructor's first use.
+    pub first: usize,
+    /// The arity of a later use which conflicts with the first.
+    pub second: usize,
+}
+impl<N: Name> fmt::Display for ArityConflict<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "ArityConflict({}, {}, {})",
+            self.name.show(),
+            self.first,
+            self.second
+        )
+    }
+}
+impl<N: Name + fmt::Debug> error::Error for ArityConflict<N> {
+    fn description(&self) -> &'static str {
+        "constructor used with different arities"
+    }
+}
+
+/// Infer the arity of every constructor from its uses in `types`, which must be consistent.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate polytype;
+/// # fn main() {
+/// # use polytype::{infer_arities, ArityConflict};
+/// let types = vec![tp!(@arrow[tp!(int), tp!(list(tp!(0)))]), tp!(list(tp!(bool)))];
+/// let arities = infer_arities(&types).expect("consistent");
+/// assert_eq!(arities["int"], 0);
+/// assert_eq!(arities["list"], 1);
+/// assert_eq!(arities["→"], 2);
+///
+/// let types = vec![tp!(list(tp!(int))), tp!(list)];
+/// assert_eq!(
+///     infer_arities(&types),
+///     Err(ArityConflict { name: "list", first: 1, second: 0 })
+/// );
+/// # }
+/// ```
+pub fn infer_arities<N: Name + Hash>(
+    types: &[Type<N>],
+) -> Result<HashMap<N, usize>, ArityConflict<N>> {
+    let mut arities = HashMap::new();
+    // a stack, so uses are visited in order.
+    let mut pending: Vec<&Type<N>> = types.iter().rev().collect();
+    while let Some(tp) = pending.pop() {
+        if let Type::Constructed(ref name, ref args) = *tp {
+            let first = *arities.entry(name.clone()).or_insert_with(|| args.len());
+            if first != args.len() {
+                return Err(ArityConflict {
+                    name: name.clone(),
+                    first,
+                    second: args.len(),
+                });
+            }
+            pending.extend(args.iter().rev());
+        }
+    }
+    Ok(arities)
+}
+
+/// A lazily instantiated [`TypeSchema`], created by [`TypeSchema::instantiate_lazy`].
+///
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`TypeSchema::instantiate_lazy`]: enum.TypeSchema.html#method.instantiate_lazy
+#[derive(Debug, Clone)]
+pub struct LazyType<'a, N: Name + 'a = &'static str> {
+    tp: &'a Type<N>,
+    substitution: Rc<HashMap<Variable, Type<N>>>,
+}
+impl<'a, N: Name> LazyType<'a, N> {
+    /// The name of the type, if it is constructed.
+    pub fn name(&self) -> Option<&'a N> {
+        match *self.tp {
+            Type::Constructed(ref name, _) => Some(name),
+            Type::Variable(_) => None,
+        }
+    }
+    /// The type, if it is a variable.
+    pub fn variable(&self) -> Option<Variable> {
+        match *self.tp {
+            Type::Constructed(..) => None,
+            Type::Variable(v) => match self.substitution.get(&v) {
+                Some(&Type::Variable(w)) => Some(w),
+                _ => Some(v),
+            },
+        }
+    }
+    /// Each argument of the type, if it is constructed.
+    pub fn args(&self) -> Vec<LazyType<'a, N>> {
+        match *self.tp {
+            Type::Constructed(_, ref args) => args
+                .iter()
+                .map(|tp| LazyType {
+                    tp,
+                    substitution: self.substitution.clone(),
+                })
+                .collect(),
+            Type::Variable(_) => Vec::new(),
+        }
+    }
+    /// Construct the whole instantiated type.
+    pub fn force(&self) -> Type<N> {
+        self.tp.substitute(&self.substitution)
+    }
+}
+
+/// A borrowed view of a [`Type`], created by [`Type::cursor`].
+///
+/// [`Type`]: enum.Type.html
+/// [`Type::cursor`]: enum.Type.html#method.cursor
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cursor<'a, N: Name + 'a = &'static str> {
+    tp: &'a Type<N>,
+}
+impl<'a, N: Name> Clone for Cursor<'a, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'a, N: Name> Copy for Cursor<'a, N> {}
+impl<'a, N: Name> Cursor<'a, N> {
+    /// The type being viewed.
+    pub fn tp(&self) -> &'a Type<N> {
+        self.tp
+    }
+    /// The name of the viewed type, if it is constructed.
+    pub fn name(&self) -> Option<&'a N> {
+        match *self.tp {
+            Type::Constructed(ref name, _) => Some(name),
+            Type::Variable(_) => None,
+        }
+    }
+    /// The viewed type, if it is a variable.
+    pub fn variable(&self) -> Option<Variable> {
+        match *self.tp {
+            Type::Constructed(..) => None,
+            Type::Variable(v) => Some(v),
+        }
+    }
+    /// Views of each argument of the viewed type. A variable has none.
+    pub fn children(&self) -> Children<'a, N> {
+        let args: &'a [Type<N>] = match *self.tp {
+            Type::Constructed(_, ref args) => args,
+            Type::Variable(_) => &[],
+        };
+        Children { inner: args.iter() }
+    }
+}
+
+/// An iterator over the arguments of a type, created by [`Cursor::children`].
+///
+/// [`Cursor::children`]: struct.Cursor.html#method.children
+#[derive(Debug, Clone)]
+pub struct Children<'a, N: Name + 'a = &'static str> {
+    inner: ::std::slice::Iter<'a, Type<N>>,
+}
+impl<'a, N: Name> Iterator for Children<'a, N> {
+    type Item = Cursor<'a, N>;
+    fn next(&mut self) -> Option<Cursor<'a, N>> {
+        self.inner.next().map(|tp| Cursor { tp })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+/// Types are totally ordered when their names are: variables come before constructed types and
+/// are ordered by number, while constructed types are ordered by name and then by arguments.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate polytype;
+/// # fn main() {
+/// let mut ts = vec![tp!(list(tp!(int))), tp!(1), tp!(bool), tp!(list(tp!(0))), tp!(0)];
+/// ts.sort();
+/// assert_eq!(
+///     ts,
+///     vec![tp!(0), tp!(1), tp!(bool), tp!(list(tp!(0))), tp!(list(tp!(int)))]
+/// );
+/// # }
+/// ```
+impl<N: Name + Ord> Ord for Type<N> {
+    fn cmp(&self, other: &Type<N>) -> Ordering {
+        match (self, other) {
+            (Type::Variable(v1), Type::Variable(v2)) => v1.cmp(v2),
+            (Type::Variable(_), Type::Constructed(..)) => Ordering::Less,
+            (Type::Constructed(..), Type::Variable(_)) => Ordering::Greater,
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
+                n1.cmp(n2).then_with(|| a1.cmp(a2))
+            }
+        }
+    }
+}
+impl<N: Name + Ord> PartialOrd for Type<N> {
+    fn partial_cmp(&self, other: &Type<N>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 impl<N: Name> fmt::Display for Type<N> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -670,3 +2559,63 @@ impl<N: Name> From<Vec<Type<N>>> for Type<N> {
         beta
     }
 }
+
+fn numbered_variable(v: Variable) -> String {
+    format!("t{}", v)
+}
+
+/// Indent `lines` between an opening and closing line.
+fn wrap_lines(open: &str, lines: Vec<String>, close: &str) -> Vec<String> {
+    let mut wrapped = Vec::with_capacity(lines.len() + 2);
+    wrapped.push(open.to_string());
+    wrapped.extend(lines.into_iter().map(|line| format!("    {}", line)));
+    wrapped.push(close.to_string());
+    wrapped
+}
+
+/// Quote `name` unless it is already a plain Prolog atom.
+fn prolog_atom(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Where a type appears in LaTeX output, which decides whether it needs parentheses.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LatexPosition {
+    /// At the top level, or to the right of an arrow.
+    Return,
+    /// To the left of an arrow.
+    Argument,
+    /// An argument of a constructor.
+    Applied,
+}
+
+/// Escape the characters of `name` which are special in LaTeX math.
+fn latex_escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '_' | '#' | '$' | '%' | '&' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\backslash{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn lettered_variable(v: Variable) -> String {
+    let letter = char::from(b'a' + (v % 26) as u8);
+    match v / 26 {
+        0 => letter.to_string(),
+        n => format!("{}{}", letter, n),
+    }
+}
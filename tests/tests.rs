@@ -365,3 +365,463 @@ fn test_parse() {
     );
     assert_eq!(t, Type::parse(&t.to_string()).expect("parse 12"));
 }
+
+#[test]
+fn test_encode_decode() {
+    let ts = vec![
+        tp!(int),
+        tp!(5),
+        tp!(@arrow[tp!(int), tp!(int)]),
+        tp!(list(tp!(@arrow[tp!(int), tp!(2)]))),
+        tp!(hashmap(tp!(str), tp!(@arrow[tp!(int), tp!(0), tp!(bool)]))),
+        tp!(@arrow[
+            tp!(@arrow[tp!(1), tp!(0), tp!(1)]),
+            tp!(1),
+            tp!(list(tp!(0))),
+            tp!(1),
+        ]),
+    ];
+    for t in ts {
+        assert_eq!(t, Type::decode(&t.encode()).expect("round trip"));
+    }
+
+    assert_eq!(
+        Type::<&'static str>::decode("c0:3:intv0"),
+        Err(DecodeError::TrailingInput(8))
+    );
+    assert_eq!(
+        Type::<&'static str>::decode("x"),
+        Err(DecodeError::UnexpectedChar(0))
+    );
+    assert_eq!(
+        Type::<&'static str>::decode("v99999"),
+        Err(DecodeError::InvalidNumber(1))
+    );
+    assert_eq!(
+        Type::<&'static str>::decode("c0:9:int"),
+        Err(DecodeError::UnexpectedEnd)
+    );
+    assert_eq!(
+        Type::<&'static str>::decode("c0:18446744073709551615:"),
+        Err(DecodeError::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn test_context_bytes() {
+    let mut ctx = Context::default();
+    for _ in 0..300 {
+        ctx.new_variable();
+    }
+    ctx.extend(3, tp!(list(tp!(@arrow[tp!(int), tp!(299)]))));
+    ctx.extend(200, tp!(hashmap(tp!(str), tp!(3))));
+    let bytes = ctx.to_bytes();
+    assert_eq!(&bytes[..2], b"\xac\x02");
+    assert_eq!(Context::from_bytes(&bytes), Ok(ctx));
+
+    let mut ctx = Context::default();
+    let skolem = ctx.new_skolem();
+    ctx.unify_labeled(&tp!(1), &tp!(list(skolem.clone())), "argument")
+        .expect("unifies");
+    ctx.set_max_substitution_size(Some(10));
+    ctx.set_dynamic("dyn");
+    ctx.add_alias("string", tp!(list(tp!(char))));
+    ctx.set_never("never");
+    let mut decoded: Context = Context::from_bytes(&ctx.to_bytes()).expect("valid encoding");
+    assert_eq!(decoded, ctx);
+    assert!(decoded.unify(&skolem, &tp!(int)).is_err());
+
+    let empty: Context = Context::default();
+    assert_eq!(empty.to_bytes(), b"\x00\x00\x00\x00\x00\x00\x00\x00");
+    assert_eq!(
+        Context::<&'static str>::from_bytes(b"\x00\x00\x00\x00\x00\x00\x00\x00\x00"),
+        Err(DecodeError::TrailingInput(8))
+    );
+    assert_eq!(
+        Context::<&'static str>::from_bytes(b"\x00\x01\x00\x02"),
+        Err(DecodeError::UnexpectedChar(3))
+    );
+    assert_eq!(
+        Context::<&'static str>::from_bytes(b"\xff\xff\x04\x00"),
+        Err(DecodeError::InvalidNumber(0))
+    );
+
+    let mut nested = b"\x01\x01\x00".to_vec();
+    for _ in 0..100_000 {
+        nested.extend_from_slice(b"\x01\x01\x04list");
+    }
+    assert_eq!(
+        Context::<&'static str>::from_bytes(&nested),
+        Err(DecodeError::TooDeep(3 + 513 * 7))
+    );
+    let nested = "c1:4:list".repeat(100_000);
+    assert_eq!(
+        Type::<&'static str>::decode(&nested),
+        Err(DecodeError::TooDeep(513 * 9))
+    );
+}
+
+#[test]
+fn test_unify_spread() {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum N {
+        Arrow,
+        Spread,
+        Tuple,
+        Int,
+        Bool,
+    }
+    impl Name for N {
+        fn arrow() -> Self {
+            N::Arrow
+        }
+        fn is_spread(&self) -> bool {
+            *self == N::Spread
+        }
+    }
+    let tint = Type::Constructed(N::Int, vec![]);
+    let tbool = Type::Constructed(N::Bool, vec![]);
+    let spread = |v| Type::Constructed(N::Spread, vec![Type::Variable(v)]);
+    let tuple = |args| Type::Constructed(N::Tuple, args);
+
+    // spread against nothing
+    let mut ctx = Context::default();
+    ctx.unify(&tuple(vec![spread(0)]), &tuple(vec![]))
+        .expect("empty spread");
+
+    // spread against mismatched arguments
+    let mut ctx = Context::default();
+    ctx.unify(
+        &tuple(vec![spread(0)]),
+        &tuple(vec![tint.clone(), tbool.clone()]),
+    )
+    .expect_err("heterogeneous spread");
+
+    // spreads on both sides
+    let mut ctx = Context::default();
+    ctx.unify(
+        &tuple(vec![Type::Variable(0), spread(1)]),
+        &tuple(vec![tint.clone(), tint.clone(), tint.clone(), spread(2)]),
+    )
+    .expect("both sides spread");
+    for v in 0..3 {
+        assert_eq!(Type::Variable(v).apply(&ctx), tint);
+    }
+}
+
+#[test]
+fn test_unify_arity_mismatch() {
+    let mut ctx = Context::default();
+    let res = ctx.unify(&tp!(tuple(tp!(0))), &tp!(tuple(tp!(int), tp!(bool))));
+    assert_eq!(
+        res,
+        Err(UnificationError::Failure(
+            tp!(tuple(tp!(0))),
+            tp!(tuple(tp!(int), tp!(bool))),
+        ))
+    );
+    let res = ctx.unify(
+        &tp!(list(tp!(tuple(tp!(0))))),
+        &tp!(list(tp!(tuple(tp!(int), tp!(bool))))),
+    );
+    assert!(res.unwrap_err().is_failure());
+    assert!(ctx.substitution().is_empty());
+}
+
+#[test]
+fn test_unify_vec_length() {
+    let three = Type::nullary("3");
+    let mut ctx = Context::default();
+    ctx.unify(
+        &Type::vec("vec", tp!(int), three.clone()),
+        &Type::vec("vec", tp!(int), tp!(0)),
+    )
+    .expect("length unifies");
+    assert_eq!(tp!(0).apply(&ctx), three);
+
+    // once bound, the length must agree.
+    let res = ctx.unify(
+        &Type::vec("vec", tp!(int), Type::nullary("2")),
+        &Type::vec("vec", tp!(int), tp!(0)),
+    );
+    assert!(res.unwrap_err().is_failure());
+    assert_eq!(
+        Type::vec("vec", tp!(int), tp!(0)).as_vec(&"vec"),
+        Some((&tp!(int), &tp!(0)))
+    );
+}
+
+#[test]
+fn test_zonk_resolves_fully() {
+    let mut ctx = Context::default();
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    let c = ctx.new_variable();
+    let d = ctx.new_variable();
+    ctx.unify(&a, &tp!(@arrow[b.clone(), c.clone()])).unwrap();
+    ctx.unify(&b, &tp!(list(d.clone()))).unwrap();
+    ctx.unify(&c, &b).unwrap();
+    ctx.unify(&d, &tp!(int)).unwrap();
+
+    let t = tp!(pair(a, tp!(4))).zonk(&ctx);
+    assert_eq!(
+        t,
+        tp!(pair(
+            tp!(@arrow[tp!(list(tp!(int))), tp!(list(tp!(int)))]),
+            tp!(4)
+        ))
+    );
+    for v in t.vars() {
+        assert!(!ctx.substitution().contains_key(&v));
+    }
+}
+
+#[test]
+fn test_solve_terminates_without_bindings() {
+    let mut ctx = Context::default();
+    ctx.set_dynamic("dyn");
+    assert_eq!(ctx.unify_progress(&tp!(dyn), &tp!(int)), Ok(false));
+    assert!(ctx.is_empty());
+    ctx.solve(vec![(tp!(dyn), tp!(int)), (tp!(0), tp!(list(tp!(dyn))))])
+        .expect("consistent constraints");
+    assert_eq!(tp!(0).apply(&ctx), tp!(list(tp!(dyn))));
+
+    let mut ctx = Context::default();
+    ctx.add_alias("string", tp!(list(tp!(char))));
+    ctx.solve(vec![
+        (tp!(string), tp!(list(tp!(char)))),
+        (tp!(0), tp!(int)),
+    ])
+    .expect("consistent constraints");
+    assert_eq!(ctx.len(), 1);
+    assert_eq!(
+        ctx.principal(vec![(tp!(string), tp!(list(tp!(1))))], &tp!(1)),
+        Ok(tp!(char))
+    );
+}
+
+#[test]
+fn test_generalize_all_resolves_chains() {
+    let mut ctx = Context::default();
+    ctx.unify(&tp!(0), &tp!(1)).unwrap();
+    ctx.unify(&tp!(1), &tp!(int)).unwrap();
+    assert_eq!(tp!(0).generalize_all(&ctx), ptp!(int));
+    assert_eq!(
+        tp!(@arrow[tp!(0), tp!(2)]).generalize_all(&ctx),
+        ptp!(2; @arrow[tp!(int), tp!(2)])
+    );
+}
+
+#[test]
+fn test_generalize_ordered_resolves_chains() {
+    let mut ctx = Context::default();
+    ctx.unify(&tp!(0), &tp!(1)).unwrap();
+    ctx.unify(&tp!(1), &tp!(int)).unwrap();
+    assert_eq!(tp!(0).generalize_ordered(&[], &ctx), ptp!(int));
+    assert_eq!(
+        tp!(@arrow[tp!(0), tp!(2)]).generalize_ordered(&[2], &ctx),
+        ptp!(2; @arrow[tp!(int), tp!(2)])
+    );
+}
+
+#[test]
+fn test_fork_disjoint_siblings() {
+    let mut prelude: Context = Context::default();
+    prelude.new_variable();
+    let parent = CowContext::new(prelude);
+    let mut children: Vec<_> = (0..3).map(|i| parent.fork(i, 4).unwrap()).collect();
+    let mut grandchildren: Vec<_> = (0..2).map(|i| children[2].fork(i, 2).unwrap()).collect();
+    assert_eq!(children[2].fork(2, 2), Err(MergeOverflow));
+    let mut seen = vec![0];
+    for child in children.iter_mut().take(2) {
+        for _ in 0..4 {
+            if let Type::Variable(v) = child.new_variable() {
+                assert!(!seen.contains(&v));
+                seen.push(v);
+            }
+        }
+    }
+    for grandchild in &mut grandchildren {
+        for _ in 0..2 {
+            if let Type::Variable(v) = grandchild.new_variable() {
+                assert!(!seen.contains(&v));
+                seen.push(v);
+            }
+        }
+    }
+    assert_eq!(seen.len(), 13);
+    assert!(seen.iter().all(|&v| v < 13));
+}
+
+#[test]
+#[should_panic]
+fn test_fork_exhausted() {
+    let parent: CowContext = CowContext::default();
+    let mut child = parent.fork(0, 2).unwrap();
+    child.new_variable();
+    child.new_variable();
+    child.new_variable();
+}
+
+#[test]
+fn test_never_mismatch_with_skolem() {
+    let mut ctx = Context::default();
+    ctx.set_never("never");
+    let a = ctx.new_skolem();
+    assert_eq!(
+        ctx.unify(&a, &tp!(never)),
+        Err(UnificationError::NeverMismatch(a.clone(), tp!(never)))
+    );
+    assert_eq!(
+        ctx.unify_overlay(&tp!(never), &a).err(),
+        Some(UnificationError::NeverMismatch(tp!(never), a))
+    );
+}
+
+#[test]
+fn test_overlay_agrees_with_unify() {
+    let mut ctx = Context::default();
+    ctx.set_dynamic("dyn");
+    ctx.set_never("never");
+    ctx.add_alias("string", tp!(list(tp!(char))));
+    ctx.set_max_substitution_size(Some(3));
+    let a = ctx.new_skolem();
+    let cases = vec![
+        (tp!(string), tp!(list(tp!(1)))),
+        (tp!(pair(tp!(dyn), tp!(2))), tp!(pair(tp!(int), tp!(bool)))),
+        (tp!(list(tp!(never))), tp!(list(tp!(int)))),
+        (a.clone(), tp!(int)),
+        (
+            tp!(@arrow[tp!(1), tp!(2), tp!(3), tp!(4)]),
+            tp!(@arrow[tp!(int), tp!(int), tp!(int), tp!(int)]),
+        ),
+    ];
+    for (t1, t2) in cases {
+        let mut expected = ctx.clone();
+        let direct = expected.unify(&t1, &t2);
+        let mut committed = ctx.clone();
+        match ctx.unify_overlay(&t1, &t2) {
+            Ok(overlay) => {
+                assert_eq!(direct, Ok(()));
+                committed.commit(overlay);
+                assert_eq!(committed, expected);
+            }
+            Err(e) => assert_eq!(direct, Err(e)),
+        }
+    }
+}
+
+#[test]
+fn test_same_with_cyclic_binding() {
+    let mut ctx = Context::default();
+    ctx.extend(0, tp!(list(tp!(0))));
+    ctx.extend(1, tp!(list(tp!(0))));
+    ctx.extend(2, tp!(int));
+    assert!(ctx.same(0, 0));
+    assert!(!ctx.same(0, 2));
+    assert!(!ctx.same(1, 2));
+    assert_eq!(
+        ctx.principal(Vec::new(), &tp!(pair(tp!(2), tp!(0)))),
+        Ok(tp!(pair(tp!(int), tp!(list(tp!(0))))))
+    );
+}
+
+#[test]
+fn test_context_change_then_composes() {
+    let changes = || {
+        let mut ctx: Context = Context::default();
+        for _ in 0..3 {
+            ctx.new_variable();
+        }
+        let mut other: Context = Context::default();
+        for _ in 0..4 {
+            other.new_variable();
+        }
+        vec![
+            ctx.merge(other.clone(), vec![1]),
+            ctx.merge(other.clone(), vec![1, 4, 6]),
+            ctx.merge(other, vec![3, 8]),
+        ]
+    };
+    let composite = changes()
+        .into_iter()
+        .fold(None, |acc: Option<ContextChange>, change| match acc {
+            Some(acc) => Some(acc.then(change)),
+            None => Some(change),
+        })
+        .unwrap();
+    let steps = changes();
+    for v in 0..16 {
+        let mut expected = Type::Variable(v);
+        for change in &steps {
+            change.reify_type(&mut expected);
+        }
+        let mut t = tp!(list(Type::Variable(v)));
+        composite.reify_type(&mut t);
+        assert_eq!(t, tp!(list(expected)));
+    }
+}
+
+#[test]
+fn test_unify_modulo_under_alias() {
+    let mut axioms = Axioms::default();
+    axioms.add_commutative("pair");
+    let mut ctx = Context::default();
+    ctx.add_alias("entry", tp!(pair(tp!(int), tp!(0))));
+
+    let t1 = tp!(list(tp!(entry(tp!(1)))));
+    let t2 = tp!(list(tp!(pair(tp!(bool), tp!(int)))));
+    assert!(ctx.clone().unify(&t1, &t2).is_err());
+    ctx.unify_modulo(&t1, &t2, &axioms)
+        .expect("unifies modulo commutativity within the alias");
+    assert_eq!(tp!(1).apply(&ctx), tp!(bool));
+
+    // the bindings of a failed order are undone before trying the other.
+    let mut ctx = Context::default();
+    let t1 = tp!(pair(tp!(2), tp!(pair(tp!(2), tp!(int)))));
+    let t2 = tp!(pair(tp!(pair(tp!(bool), tp!(int))), tp!(bool)));
+    ctx.unify_modulo(&t1, &t2, &axioms)
+        .expect("unifies swapped");
+    assert_eq!(tp!(2).apply(&ctx), tp!(bool));
+    assert_eq!(ctx.len(), 1);
+}
+
+#[test]
+fn test_check_directional_never_and_skolem() {
+    let mut ctx = Context::default();
+    ctx.set_never("never");
+    assert_eq!(
+        ctx.check(&tp!(list(tp!(int))), &tp!(list(tp!(never)))),
+        Err(TypeError::Mismatch {
+            expected: tp!(int),
+            actual: tp!(never),
+        })
+    );
+    assert_eq!(
+        ctx.check(&tp!(never), &tp!(int)),
+        Err(TypeError::Mismatch {
+            expected: tp!(never),
+            actual: tp!(int),
+        })
+    );
+
+    let a = ctx.new_skolem();
+    assert_eq!(
+        ctx.check(
+            &tp!(pair(a.clone(), tp!(int))),
+            &tp!(pair(tp!(bool), tp!(int)))
+        ),
+        Err(TypeError::Mismatch {
+            expected: a.clone(),
+            actual: tp!(bool),
+        })
+    );
+    let b = ctx.new_skolem();
+    assert_eq!(
+        ctx.check(&b, &a),
+        Err(TypeError::Mismatch {
+            expected: b,
+            actual: a,
+        })
+    );
+}